@@ -0,0 +1,196 @@
+//! Rough timings for the per-answer hot path: selection, counts and recording.
+//!
+//! Run with `cargo bench -p tt_core`. Uses a plain timing loop rather than a
+//! benchmark framework, so numbers are indicative only. The unlock check,
+//! selection and counts are each shown next to `baseline`, the same code with
+//! the set-based unlock check the table bitmask replaced, so the two can be
+//! compared on the same machine. Recording an answer also journals it and
+//! counts it toward the day, so it is timed on its own.
+
+use baseline::Baseline;
+use chrono::Utc;
+use std::hint::black_box;
+use std::time::Instant;
+use tt_core::problem::{generate_all_problems, Problem, ProblemStats, TABLE_ORDER};
+use tt_core::spaced_rep::SpacedRepetition;
+
+const ITERATIONS: u32 = 20_000;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // Warm up caches and the allocator before timing.
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+    println!("{:<32} {:>10.2?} / iter", name, per_iter);
+}
+
+/// A learner partway through: the first half of the tables unlocked.
+fn partly_learned() -> SpacedRepetition {
+    let mut sr = SpacedRepetition::new();
+    let halfway = Some(TABLE_ORDER[6]);
+    'answering: loop {
+        for problem in generate_all_problems() {
+            if sr.next_table_to_unlock() == halfway {
+                break 'answering;
+            }
+            sr.record_answer(&problem, true, 2.0);
+        }
+    }
+    sr
+}
+
+/// The selection and counts as `SpacedRepetition` does them, except that
+/// every unlock check builds a set of the unlocked tables, as before the
+/// table bitmask. Works on a copy of the stats, since the real ones are
+/// private.
+mod baseline {
+    use super::*;
+    use std::collections::HashSet;
+
+    pub struct Baseline {
+        stats: Vec<ProblemStats>,
+        unlocked_tables: usize,
+    }
+
+    impl Baseline {
+        pub fn snapshot(sr: &SpacedRepetition) -> Self {
+            let stats = generate_all_problems()
+                .iter()
+                .filter_map(|p| sr.stats_for(p).cloned())
+                .collect();
+            let unlocked_tables = sr
+                .next_table_to_unlock()
+                .and_then(|next| TABLE_ORDER.iter().position(|&t| t == next))
+                .unwrap_or(TABLE_ORDER.len());
+            Self {
+                stats,
+                unlocked_tables,
+            }
+        }
+
+        fn unlocked_table_set(&self) -> HashSet<u8> {
+            TABLE_ORDER
+                .iter()
+                .take(self.unlocked_tables)
+                .copied()
+                .collect()
+        }
+
+        pub fn is_problem_unlocked(&self, problem: &Problem) -> bool {
+            let unlocked = self.unlocked_table_set();
+            let (a, b) = problem.tables_required();
+            unlocked.contains(&a) || unlocked.contains(&b)
+        }
+
+        fn hardest(&self, last: Option<&Problem>, due_only: bool) -> Option<Problem> {
+            let now = Utc::now();
+            self.stats
+                .iter()
+                .filter(|s| {
+                    (!due_only || s.is_due_at(now))
+                        && self.is_problem_unlocked(&s.problem)
+                        && last.is_none_or(|l| s.problem != *l)
+                })
+                .min_by(|a, b| a.ease_factor.total_cmp(&b.ease_factor))
+                .map(|s| s.problem)
+        }
+
+        pub fn get_next_problem(&self, last: Option<&Problem>) -> Option<Problem> {
+            self.hardest(last, true)
+        }
+
+        pub fn get_extra_practice_problem(&self, last: Option<&Problem>) -> Option<Problem> {
+            self.hardest(last, false)
+        }
+
+        pub fn due_count(&self) -> usize {
+            let now = Utc::now();
+            self.stats
+                .iter()
+                .filter(|s| self.is_problem_unlocked(&s.problem) && s.is_due_at(now))
+                .count()
+        }
+
+        pub fn mastered_count(&self) -> usize {
+            self.stats
+                .iter()
+                .filter(|s| self.is_problem_unlocked(&s.problem) && s.is_mastered())
+                .count()
+        }
+
+        pub fn unlocked_problems(&self) -> usize {
+            self.stats
+                .iter()
+                .filter(|s| self.is_problem_unlocked(&s.problem))
+                .count()
+        }
+    }
+}
+
+fn main() {
+    let fresh = SpacedRepetition::new();
+    let learned = partly_learned();
+    let last = Problem::new(7, 8);
+    let all = generate_all_problems();
+
+    for (label, sr) in [("fresh", &fresh), ("partly learned", &learned)] {
+        let old = Baseline::snapshot(sr);
+        println!("{}:", label);
+        bench("  is_unlocked, every fact", || {
+            for problem in &all {
+                black_box(sr.is_unlocked(black_box(problem)));
+            }
+        });
+        bench("    baseline", || {
+            for problem in &all {
+                black_box(old.is_problem_unlocked(black_box(problem)));
+            }
+        });
+        bench("  get_next_problem", || {
+            black_box(sr.get_next_problem(black_box(Some(&last)), None));
+        });
+        bench("    baseline", || {
+            black_box(old.get_next_problem(black_box(Some(&last))));
+        });
+        bench("  get_extra_practice_problem", || {
+            black_box(sr.get_extra_practice_problem(black_box(Some(&last)), None));
+        });
+        bench("    baseline", || {
+            black_box(old.get_extra_practice_problem(black_box(Some(&last))));
+        });
+        bench("  due_count", || {
+            black_box(sr.due_count());
+        });
+        bench("    baseline", || {
+            black_box(old.due_count());
+        });
+        bench("  mastered_count", || {
+            black_box(sr.mastered_count());
+        });
+        bench("    baseline", || {
+            black_box(old.mastered_count());
+        });
+        bench("  unlocked_problems", || {
+            black_box(sr.unlocked_problems());
+        });
+        bench("    baseline", || {
+            black_box(old.unlocked_problems());
+        });
+        bench("  grid_status", || {
+            black_box(sr.grid_status());
+        });
+    }
+
+    let mut sr = partly_learned();
+    let mut correct = false;
+    println!("answering:");
+    bench("  record_answer", || {
+        correct = !correct;
+        sr.record_answer(black_box(&last), correct, 2.0);
+    });
+}
@@ -0,0 +1,148 @@
+use crate::problem::Problem;
+use crate::rng::SeededRng;
+use serde::{Deserialize, Serialize};
+
+/// How an answer was given. A picked answer was recognised rather than
+/// recalled, which is weaker evidence that the fact is known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerMode {
+    #[default]
+    Typed,
+    MultipleChoice,
+}
+
+/// A problem with a shuffled set of options, one of which is correct.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultipleChoice {
+    pub problem: Problem,
+    pub options: Vec<u32>,
+}
+
+impl MultipleChoice {
+    /// Builds `count` options (at least 2) from the answer and its most
+    /// plausible wrong answers. The same seed always gives the same options.
+    pub fn new(problem: Problem, count: usize, seed: u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        let wanted = count.max(2) - 1;
+
+        // Draw from a few more of the likeliest mistakes than needed, so the
+        // same distractors don't appear every time.
+        let mut pool = distractors(&problem);
+        pool.truncate(wanted + 2);
+        let mut filler = problem.answer() + 3;
+        while pool.len() < wanted {
+            if !pool.contains(&filler) {
+                pool.push(filler);
+            }
+            filler += 1;
+        }
+        rng.shuffle(&mut pool);
+        pool.truncate(wanted);
+
+        let mut options = pool;
+        options.push(problem.answer());
+        rng.shuffle(&mut options);
+        Self { problem, options }
+    }
+
+    pub fn is_correct(&self, choice: u32) -> bool {
+        choice == self.problem.answer()
+    }
+}
+
+/// Wrong answers a learner is likely to give, most likely first: the product
+/// with its digits swapped, neighbouring multiples in either table, adding
+/// instead of multiplying, and being off by ten.
+pub fn distractors(problem: &Problem) -> Vec<u32> {
+    let (a, b) = (problem.a as u32, problem.b as u32);
+    let answer = problem.answer();
+
+    let swapped = (answer >= 10)
+        .then(|| answer.to_string().chars().rev().collect::<String>())
+        .and_then(|s| s.parse().ok());
+
+    let candidates = [
+        swapped,
+        Some(a * (b + 1)),
+        b.checked_sub(1).map(|b| a * b),
+        Some((a + 1) * b),
+        a.checked_sub(1).map(|a| a * b),
+        Some(a + b),
+        Some(answer + 10),
+        answer.checked_sub(10),
+        Some(a * (b + 2)),
+        b.checked_sub(2).map(|b| a * b),
+        Some(answer + 1),
+        answer.checked_sub(1),
+        Some(answer + 2),
+    ];
+
+    let mut out: Vec<u32> = Vec::new();
+    for c in candidates.into_iter().flatten() {
+        if c > 0 && c != answer && !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::generate_all_problems;
+
+    #[test]
+    fn every_fact_gets_distinct_options_with_one_right_answer() {
+        for problem in generate_all_problems() {
+            for seed in 0..8 {
+                let choice = MultipleChoice::new(problem, 4, seed);
+                let mut options = choice.options.clone();
+                options.sort_unstable();
+                options.dedup();
+                assert_eq!(options.len(), 4, "{:?} seed {}", problem, seed);
+                assert!(options.iter().all(|&o| o > 0));
+                let right = choice.options.iter().filter(|&&o| choice.is_correct(o));
+                assert_eq!(right.count(), 1, "{:?} seed {}", problem, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn distractors_are_likely_mistakes_likeliest_first() {
+        // Digits swapped, then the neighbouring multiples, then adding.
+        let wrong = distractors(&Problem::new(7, 8));
+        assert_eq!(wrong[..6], [65, 63, 49, 64, 48, 15]);
+        assert!(!wrong.contains(&56));
+
+        let mut unique = wrong.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), wrong.len());
+    }
+
+    #[test]
+    fn small_answers_are_padded_to_the_requested_count() {
+        let choice = MultipleChoice::new(Problem::new(1, 1), 6, 3);
+        assert_eq!(choice.options.len(), 6);
+        assert!(choice.options.contains(&1));
+        // At least two options, however few are asked for.
+        assert_eq!(
+            MultipleChoice::new(Problem::new(1, 1), 0, 3).options.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_options() {
+        let problem = Problem::new(6, 7);
+        assert_eq!(
+            MultipleChoice::new(problem, 4, 42),
+            MultipleChoice::new(problem, 4, 42)
+        );
+        let shown: std::collections::HashSet<_> = (0..20)
+            .map(|seed| MultipleChoice::new(problem, 4, seed).options)
+            .collect();
+        assert!(shown.len() > 1);
+    }
+}
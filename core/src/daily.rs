@@ -0,0 +1,321 @@
+use crate::journal::Tally;
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Time counted per answer is capped, so a learner who walks away mid-problem
+/// doesn't get an hour of practice credited.
+pub(crate) const MAX_SECS_PER_ANSWER: f64 = 60.0;
+/// A streak freeze is earned for every this many consecutive days.
+const DAYS_PER_FREEZE: u32 = 7;
+const MAX_FREEZES: u32 = 2;
+
+/// Minutes east of UTC on this machine's clock right now.
+pub fn local_utc_offset_mins() -> i32 {
+    Local::now().offset().local_minus_utc() / 60
+}
+
+/// The calendar day `at` falls on for a clock `utc_offset_mins` minutes east
+/// of UTC. Without an offset, or with one no clock has, this machine's clock
+/// decides.
+pub fn day_of(at: DateTime<Utc>, utc_offset_mins: Option<i32>) -> NaiveDate {
+    match utc_offset_mins
+        .and_then(|mins| mins.checked_mul(60))
+        .and_then(FixedOffset::east_opt)
+    {
+        Some(offset) => at.with_timezone(&offset).date_naive(),
+        None => at.with_timezone(&Local).date_naive(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DailyGoal {
+    CorrectAnswers(u32),
+    Minutes(u32),
+}
+
+impl Default for DailyGoal {
+    fn default() -> Self {
+        DailyGoal::CorrectAnswers(20)
+    }
+}
+
+impl DailyGoal {
+    pub fn target(&self) -> u32 {
+        match *self {
+            DailyGoal::CorrectAnswers(n) | DailyGoal::Minutes(n) => n,
+        }
+    }
+
+    /// Progress toward the goal in the goal's own unit.
+    pub fn progress(&self, day: &DayActivity) -> u32 {
+        match self {
+            DailyGoal::CorrectAnswers(_) => day.correct,
+            DailyGoal::Minutes(_) => (day.practice_secs / 60.0) as u32,
+        }
+    }
+
+    pub fn is_met(&self, day: &DayActivity) -> bool {
+        self.progress(day) >= self.target()
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            DailyGoal::CorrectAnswers(n) => format!("{} correct answers", n),
+            DailyGoal::Minutes(n) => format!("{} minutes", n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayActivity {
+    pub correct: u32,
+    pub wrong: u32,
+    pub practice_secs: f64,
+    /// The goal was met for this day by spending a streak freeze.
+    pub frozen: bool,
+    /// The goal was met on this day. Stored so that changing the goal later
+    /// doesn't rewrite the past.
+    pub goal_met: bool,
+    /// Facts mastered after the day's last answer. `None` for days from
+    /// before this was kept.
+    pub mastered: Option<u32>,
+}
+
+/// Today's standing, as reported by the frontends.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySummary {
+    pub goal: DailyGoal,
+    pub done: u32,
+    pub target: u32,
+    pub met: bool,
+    pub day_streak: u32,
+    pub freezes: u32,
+}
+
+/// A day's tally and the freezes as they were before an answer, to put back
+/// if the answer is undone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DaySnapshot {
+    date: NaiveDate,
+    activity: Option<DayActivity>,
+    freezes: u32,
+}
+
+/// Per-day practice calendar with a daily goal and a consecutive-days streak.
+///
+/// A day counts toward the streak if its goal was met or it was covered by a
+/// streak freeze. Today only breaks the streak once it is over, so an
+/// unfinished today doesn't show a zero streak in the morning.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyProgress {
+    pub goal: DailyGoal,
+    /// Spend freezes automatically to cover missed days.
+    pub freeze_enabled: bool,
+    pub freezes: u32,
+    days: BTreeMap<NaiveDate, DayActivity>,
+}
+
+impl DailyProgress {
+    /// Changes the goal. Today is re-checked against the new goal; earlier days
+    /// keep whatever they achieved.
+    pub fn set_goal(&mut self, goal: DailyGoal, today: NaiveDate) {
+        self.goal = goal;
+        if let Some(day) = self.days.get_mut(&today) {
+            day.goal_met |= goal.is_met(day);
+        }
+    }
+
+    pub fn record(&mut self, date: NaiveDate, correct: bool, response_secs: f64) {
+        self.record_with_snapshot(date, correct, response_secs);
+    }
+
+    /// Records an answer and returns the day as it was before it. Freezes
+    /// spent on missed days stay spent, as the next answer would spend them
+    /// anyway.
+    pub(crate) fn record_with_snapshot(
+        &mut self,
+        date: NaiveDate,
+        correct: bool,
+        response_secs: f64,
+    ) -> DaySnapshot {
+        self.apply_freezes(date);
+        let snapshot = DaySnapshot {
+            date,
+            activity: self.days.get(&date).copied(),
+            freezes: self.freezes,
+        };
+
+        let goal = self.goal;
+        let day = self.days.entry(date).or_default();
+        let was_met = day.goal_met;
+        if correct {
+            day.correct += 1;
+        } else {
+            day.wrong += 1;
+        }
+        day.practice_secs += response_secs.clamp(0.0, MAX_SECS_PER_ANSWER);
+        day.goal_met |= goal.is_met(day);
+
+        if day.goal_met && !was_met {
+            let streak = self.day_streak(date);
+            if streak > 0 && streak.checked_rem(DAYS_PER_FREEZE) == Some(0) {
+                self.freezes = (self.freezes + 1).min(MAX_FREEZES);
+            }
+        }
+        snapshot
+    }
+
+    /// Combines another device's calendar into this one; see
+    /// `SpacedRepetition::merge`. `only_mine` and `only_theirs` are the
+    /// answers, by day, that only this side or only the other side has.
+    /// The goal and freeze setting stay as they are here.
+    pub(crate) fn merge(
+        &mut self,
+        other: &DailyProgress,
+        only_mine: &BTreeMap<NaiveDate, Tally>,
+        only_theirs: &BTreeMap<NaiveDate, Tally>,
+    ) {
+        let none = Tally::default();
+        for (&date, theirs) in &other.days {
+            let mine = self.days.entry(date).or_default();
+            let gained = only_theirs.get(&date).unwrap_or(&none);
+            let lost = only_mine.get(&date).unwrap_or(&none);
+            mine.correct = (mine.correct + gained.correct).max(theirs.correct + lost.correct);
+            mine.wrong = (mine.wrong + gained.wrong).max(theirs.wrong + lost.wrong);
+            mine.practice_secs = (mine.practice_secs + gained.practice_secs)
+                .max(theirs.practice_secs + lost.practice_secs);
+            mine.frozen |= theirs.frozen;
+            mine.goal_met |= theirs.goal_met;
+            mine.mastered = mine.mastered.max(theirs.mastered);
+        }
+        self.freezes = self.freezes.max(other.freezes);
+    }
+
+    /// Takes undone answers back out of their days. A day whose goal was
+    /// only met by them is no longer met.
+    pub(crate) fn forget(&mut self, answers: &BTreeMap<NaiveDate, Tally>) {
+        let goal = self.goal;
+        for (date, tally) in answers {
+            if let Some(day) = self.days.get_mut(date) {
+                day.correct = day.correct.saturating_sub(tally.correct);
+                day.wrong = day.wrong.saturating_sub(tally.wrong);
+                day.practice_secs = (day.practice_secs - tally.practice_secs).max(0.0);
+                day.goal_met &= goal.is_met(day);
+            }
+        }
+    }
+
+    /// Notes how many facts were mastered as of the latest answer on `date`.
+    pub(crate) fn set_mastered(&mut self, date: NaiveDate, mastered: u32) {
+        if let Some(day) = self.days.get_mut(&date) {
+            day.mastered = Some(mastered);
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: DaySnapshot) {
+        match snapshot.activity {
+            Some(activity) => self.days.insert(snapshot.date, activity),
+            None => self.days.remove(&snapshot.date),
+        };
+        self.freezes = snapshot.freezes;
+    }
+
+    /// Covers the days missed since the last practice with freezes, if there
+    /// are enough of them to bridge the whole gap.
+    fn apply_freezes(&mut self, today: NaiveDate) {
+        if !self.freeze_enabled || self.freezes == 0 {
+            return;
+        }
+        let Some((&last, activity)) = self.days.range(..today).next_back() else {
+            return;
+        };
+        if !(activity.goal_met || activity.frozen) {
+            return;
+        }
+        let missed = (today - last).num_days() - 1;
+        if missed <= 0 || missed > self.freezes as i64 {
+            return;
+        }
+        for offset in 1..=missed {
+            let day = last + Days::new(offset as u64);
+            self.days.entry(day).or_default().frozen = true;
+        }
+        self.freezes -= missed as u32;
+    }
+
+    pub fn activity(&self, date: NaiveDate) -> DayActivity {
+        self.days.get(&date).copied().unwrap_or_default()
+    }
+
+    /// Every day with recorded activity, oldest first.
+    pub fn calendar(&self) -> impl Iterator<Item = (NaiveDate, &DayActivity)> {
+        self.days.iter().map(|(d, a)| (*d, a))
+    }
+
+    pub fn day_streak(&self, today: NaiveDate) -> u32 {
+        let counts =
+            |date: &NaiveDate| self.days.get(date).is_some_and(|d| d.goal_met || d.frozen);
+
+        let mut day = if counts(&today) {
+            today
+        } else {
+            match today.pred_opt() {
+                Some(d) => d,
+                None => return 0,
+            }
+        };
+        let mut streak = 0;
+        while counts(&day) {
+            streak += 1;
+            match day.pred_opt() {
+                Some(d) => day = d,
+                None => break,
+            }
+        }
+        streak
+    }
+
+    pub fn summary(&self, today: NaiveDate) -> DailySummary {
+        let activity = self.activity(today);
+        DailySummary {
+            goal: self.goal,
+            done: self.goal.progress(&activity),
+            target: self.goal.target(),
+            met: activity.goal_met,
+            day_streak: self.day_streak(today),
+            freezes: self.freezes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_moment_falls_on_the_day_of_each_clock() {
+        let at = DateTime::parse_from_rfc3339("2024-03-10T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(day_of(at, Some(0)), day(2024, 3, 10));
+        assert_eq!(day_of(at, Some(60)), day(2024, 3, 11));
+        assert_eq!(day_of(at, Some(-11 * 60)), day(2024, 3, 10));
+        // No clock is a day off UTC, so that falls back to this machine's.
+        let local = at.with_timezone(&Local).date_naive();
+        assert_eq!(day_of(at, Some(24 * 60)), local);
+        assert_eq!(day_of(at, None), local);
+    }
+
+    #[test]
+    fn answers_count_toward_the_learners_day() {
+        let at = Utc::now();
+        let mut progress = DailyProgress::default();
+        progress.record(day_of(at, Some(14 * 60)), true, 2.0);
+        assert_eq!(progress.summary(day_of(at, Some(14 * 60))).done, 1);
+        assert_ne!(day_of(at, Some(14 * 60)), day_of(at, Some(-12 * 60)));
+        assert_eq!(progress.summary(day_of(at, Some(-12 * 60))).done, 0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::problem::Problem;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A hand-picked set of tables and/or individual facts to practise.
+///
+/// A problem belongs to the focus if either of its factors is one of the chosen
+/// tables, or if it is one of the chosen facts exactly (6×7 does not imply 7×6).
+/// Focused practice ignores table unlocking: if a teacher picks the 8× table, the
+/// learner gets the 8× table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Focus {
+    #[serde(default)]
+    pub tables: Vec<u8>,
+    #[serde(default)]
+    pub facts: Vec<Problem>,
+}
+
+impl Focus {
+    pub fn new(tables: Vec<u8>, facts: Vec<Problem>) -> Self {
+        let mut focus = Self { tables, facts };
+        focus.tables.retain(|t| (1..=12).contains(t));
+        focus.tables.sort_unstable();
+        focus.tables.dedup();
+        // Keeps the facts in the order they were picked, dropping repeats.
+        let mut seen = HashSet::new();
+        focus
+            .facts
+            .retain(|p| (1..=12).contains(&p.a) && (1..=12).contains(&p.b) && seen.insert(*p));
+        focus
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.facts.is_empty()
+    }
+
+    pub fn contains(&self, problem: &Problem) -> bool {
+        self.tables.contains(&problem.a)
+            || self.tables.contains(&problem.b)
+            || self.facts.contains(problem)
+    }
+
+    /// Short human-readable description, e.g. "6×, 7× and 8 × 6".
+    pub fn label(&self) -> String {
+        let parts: Vec<String> = self
+            .tables
+            .iter()
+            .map(|t| format!("{}×", t))
+            .chain(self.facts.iter().map(|p| format!("{} × {}", p.a, p.b)))
+            .collect();
+
+        match parts.split_last() {
+            None => String::new(),
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        }
+    }
+
+    /// Parses a comma-separated list of facts such as "6x7, 7 × 8, 8*6".
+    /// Returns `None` if any entry is not a valid fact.
+    pub fn parse_facts(input: &str) -> Option<Vec<Problem>> {
+        input
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Problem::from_key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_drops_repeated_facts_that_are_not_adjacent() {
+        let focus = Focus::new(
+            vec![],
+            vec![Problem::new(7, 8), Problem::new(3, 4), Problem::new(7, 8)],
+        );
+        assert_eq!(focus.facts, vec![Problem::new(7, 8), Problem::new(3, 4)]);
+    }
+
+    #[test]
+    fn new_drops_out_of_range_tables_and_facts() {
+        let focus = Focus::new(
+            vec![0, 8, 13, 8],
+            vec![Problem::new(0, 4), Problem::new(6, 7)],
+        );
+        assert_eq!(focus.tables, vec![8]);
+        assert_eq!(focus.facts, vec![Problem::new(6, 7)]);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::problem::{Problem, ProblemStats};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// A table with its own shortcut: ×1, ×2, ×10, and ×11 up to 9.
+    Rule,
+    /// One more group than a known fact: 7×8 = 7×7 + 7.
+    AddGroup,
+    /// One group fewer than a known fact: 9×6 = 10×6 − 6.
+    TakeAwayGroup,
+    /// Double a known fact with half the groups: 8×6 is double 4×6.
+    Double,
+    /// Half of the ×10 fact: 7×5 is half of 7×10.
+    HalfOfTen,
+}
+
+/// A way to work out a fact from something easier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hint {
+    pub strategy: Strategy,
+    /// The fact the strategy builds on. `None` for rules.
+    pub anchor: Option<Problem>,
+    pub text: String,
+}
+
+/// A fact seen as `size` groups counted `count` times, where `count` is the
+/// factor the strategies change. `count_first` keeps the learner's order
+/// when writing facts out.
+struct View {
+    size: u8,
+    count: u8,
+    count_first: bool,
+}
+
+impl View {
+    fn fact(&self, count: u8) -> String {
+        if self.count_first {
+            format!("{}×{}", count, self.size)
+        } else {
+            format!("{}×{}", self.size, count)
+        }
+    }
+
+    fn problem(&self, count: u8) -> Problem {
+        if self.count_first {
+            Problem::new(count, self.size)
+        } else {
+            Problem::new(self.size, count)
+        }
+    }
+}
+
+/// Picks a strategy for `problem`. Tables with their own shortcut get that
+/// rule; otherwise the strategy whose anchor fact is best known wins, so the
+/// hint builds on something the learner can already recall. `stats` looks up
+/// how well a fact is known.
+pub fn hint_for<'a>(
+    problem: &Problem,
+    stats: impl Fn(&Problem) -> Option<&'a ProblemStats>,
+) -> Hint {
+    let views = [
+        View {
+            size: problem.a,
+            count: problem.b,
+            count_first: false,
+        },
+        View {
+            size: problem.b,
+            count: problem.a,
+            count_first: true,
+        },
+    ];
+
+    if let Some(hint) = views.iter().find_map(|v| rule(problem, v)) {
+        return hint;
+    }
+
+    // Candidates in order of how easy the step is, so ties in how well the
+    // anchors are known go to the simpler strategy.
+    let mut candidates = Vec::new();
+    for v in &views {
+        if v.count == 9 {
+            candidates.push(take_away_group(v, 10));
+        }
+        if v.count == 5 {
+            candidates.push(half_of_ten(v));
+        }
+    }
+    for v in &views {
+        if v.count >= 4 && v.count % 2 == 0 {
+            candidates.push(double(v));
+        }
+    }
+    for v in &views {
+        candidates.extend(add_group(v));
+        // ×9 already has the take away from ×10 up front.
+        if v.count < 12 && v.count != 9 {
+            candidates.push(take_away_group(v, v.count + 1));
+        }
+    }
+
+    let familiarity = |hint: &Hint| {
+        let Some(anchor) = &hint.anchor else {
+            return 0;
+        };
+        let practised = stats(anchor).map_or(0, |s| {
+            match (s.is_mastered(), s.best_tier, s.times_correct) {
+                (true, _, _) => 3,
+                (false, tier, _) if tier > 0 => 2,
+                (false, _, n) if n > 0 => 1,
+                _ => 0,
+            }
+        });
+        // Facts with a rule of their own are easy even before they're practised.
+        let easy = if has_rule(anchor) { 2 } else { 0 };
+        practised.max(easy)
+    };
+    // `max_by_key` keeps the last maximum, so search from the simplest end.
+    candidates
+        .into_iter()
+        .rev()
+        .max_by_key(familiarity)
+        .unwrap_or_else(|| take_away_group(&views[0], views[0].count.saturating_add(1)))
+}
+
+fn has_rule(problem: &Problem) -> bool {
+    let (a, b) = (problem.a, problem.b);
+    [1, 2, 10].contains(&a) || [1, 2, 10].contains(&b) || (a == 11 && b <= 9) || (b == 11 && a <= 9)
+}
+
+fn rule(problem: &Problem, v: &View) -> Option<Hint> {
+    let (size, answer) = (v.size, problem.answer());
+    let text = match v.count {
+        1 => format!("Anything times 1 stays the same: {} = {}", v.fact(1), size),
+        10 => format!("Times 10 puts a 0 on the end: {} = {}", v.fact(10), answer),
+        2 => format!(
+            "{} is double {}: {} + {} = {}",
+            v.fact(2),
+            size,
+            size,
+            size,
+            answer
+        ),
+        11 if size <= 9 => format!(
+            "11 times a single digit repeats it: {} = {}",
+            v.fact(11),
+            answer
+        ),
+        _ => return None,
+    };
+    Some(Hint {
+        strategy: Strategy::Rule,
+        anchor: None,
+        text,
+    })
+}
+
+/// `None` below ×2, where there's no smaller fact to add a group to.
+fn add_group(v: &View) -> Option<Hint> {
+    let fewer = v.count.checked_sub(1).filter(|&n| n > 0)?;
+    let anchor = v.problem(fewer);
+    Some(Hint {
+        strategy: Strategy::AddGroup,
+        anchor: Some(anchor),
+        text: format!(
+            "{} = {} + {} = {} + {}",
+            v.fact(v.count),
+            v.fact(fewer),
+            v.size,
+            anchor.answer(),
+            v.size
+        ),
+    })
+}
+
+fn take_away_group(v: &View, from: u8) -> Hint {
+    let anchor = v.problem(from);
+    Hint {
+        strategy: Strategy::TakeAwayGroup,
+        anchor: Some(anchor),
+        text: format!(
+            "{} = {} − {} = {} − {}",
+            v.fact(v.count),
+            v.fact(from),
+            v.size,
+            anchor.answer(),
+            v.size
+        ),
+    }
+}
+
+fn double(v: &View) -> Hint {
+    let anchor = v.problem(v.count / 2);
+    Hint {
+        strategy: Strategy::Double,
+        anchor: Some(anchor),
+        text: format!(
+            "Double {} for {}: {} + {}",
+            v.fact(v.count / 2),
+            v.fact(v.count),
+            anchor.answer(),
+            anchor.answer()
+        ),
+    }
+}
+
+fn half_of_ten(v: &View) -> Hint {
+    let anchor = v.problem(10);
+    Hint {
+        strategy: Strategy::HalfOfTen,
+        anchor: Some(anchor),
+        text: format!(
+            "{} is half of {}: half of {}",
+            v.fact(5),
+            v.fact(10),
+            anchor.answer()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hint_knowing(problem: Problem, known: &[ProblemStats]) -> Hint {
+        hint_for(&problem, |p| known.iter().find(|s| s.problem == *p))
+    }
+
+    fn mastered(problem: Problem) -> ProblemStats {
+        ProblemStats {
+            consecutive_correct: 3,
+            times_correct: 3,
+            ..ProblemStats::new(problem)
+        }
+    }
+
+    #[test]
+    fn tables_with_a_shortcut_get_their_rule() {
+        for problem in [Problem::new(6, 10), Problem::new(2, 7), Problem::new(11, 4)] {
+            let hint = hint_knowing(problem, &[]);
+            assert_eq!(hint.strategy, Strategy::Rule);
+            assert_eq!(hint.anchor, None);
+        }
+    }
+
+    #[test]
+    fn nines_take_a_group_away_from_ten() {
+        let hint = hint_knowing(Problem::new(9, 7), &[]);
+        assert_eq!(hint.strategy, Strategy::TakeAwayGroup);
+        assert_eq!(hint.anchor, Some(Problem::new(10, 7)));
+        assert_eq!(hint.text, "9×7 = 10×7 − 7 = 70 − 7");
+    }
+
+    #[test]
+    fn fives_are_half_of_ten() {
+        let hint = hint_knowing(Problem::new(7, 5), &[]);
+        assert_eq!(hint.strategy, Strategy::HalfOfTen);
+        assert_eq!(hint.anchor, Some(Problem::new(7, 10)));
+    }
+
+    #[test]
+    fn ties_go_to_the_simplest_step() {
+        let hint = hint_knowing(Problem::new(7, 8), &[]);
+        assert_eq!(hint.strategy, Strategy::Double);
+        assert_eq!(hint.anchor, Some(Problem::new(7, 4)));
+    }
+
+    #[test]
+    fn the_best_known_anchor_wins() {
+        let hint = hint_knowing(Problem::new(7, 8), &[mastered(Problem::new(7, 7))]);
+        assert_eq!(hint.strategy, Strategy::AddGroup);
+        assert_eq!(hint.anchor, Some(Problem::new(7, 7)));
+        assert_eq!(hint.text, "7×8 = 7×7 + 7 = 49 + 7");
+
+        let learning = ProblemStats {
+            best_tier: 1,
+            ..ProblemStats::new(Problem::new(6, 8))
+        };
+        let hint = hint_knowing(
+            Problem::new(7, 8),
+            &[mastered(Problem::new(8, 8)), learning],
+        );
+        assert_eq!(hint.strategy, Strategy::TakeAwayGroup);
+        assert_eq!(hint.anchor, Some(Problem::new(8, 8)));
+    }
+
+    #[test]
+    fn no_group_is_added_below_two() {
+        let v = View {
+            size: 7,
+            count: 1,
+            count_first: false,
+        };
+        assert_eq!(add_group(&v), None);
+        assert_eq!(add_group(&View { count: 0, ..v }), None);
+    }
+}
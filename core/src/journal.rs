@@ -0,0 +1,240 @@
+//! The answer journal: the most recent answers, each with an id and the time
+//! it was given.
+//!
+//! Ids make answers idempotent, so a client that lost its connection can send
+//! a whole session again and only the answers the server hasn't seen are
+//! applied. Answers recorded directly get an id made from their time and fact.
+
+use crate::choice::AnswerMode;
+use crate::daily::{day_of, MAX_SECS_PER_ANSWER};
+use crate::problem::Problem;
+use crate::slip::Mistake;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Answers kept in the journal. Older ones are dropped, so a replay older
+/// than this many answers could be applied twice.
+pub const JOURNAL_LIMIT: usize = 2000;
+
+/// One answer as it was recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    /// The fact as it was asked.
+    pub problem: Problem,
+    pub correct: bool,
+    /// Why a wrong answer was wrong, when the answer itself was known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mistake: Option<Mistake>,
+    pub response_secs: f64,
+    pub mode: AnswerMode,
+    pub answered_at: DateTime<Utc>,
+    /// Minutes east of UTC on the learner's clock when they answered, which
+    /// decides the day the answer counts for.
+    pub utc_offset_mins: Option<i32>,
+}
+
+impl ClientAnswer {
+    pub(crate) fn to_entry(&self, mistake: Option<Mistake>) -> JournalEntry {
+        JournalEntry {
+            id: self.id.clone(),
+            problem: self.problem,
+            correct: mistake.is_none(),
+            mistake,
+            response_secs: self.response_secs,
+            mode: self.mode,
+            answered_at: self.answered_at,
+            utc_offset_mins: self.utc_offset_mins,
+        }
+    }
+}
+
+impl JournalEntry {
+    /// An id for an answer that didn't come with one.
+    pub(crate) fn local_id(problem: &Problem, at: DateTime<Utc>) -> String {
+        format!(
+            "{}-{}",
+            at.timestamp_nanos_opt()
+                .unwrap_or_else(|| at.timestamp_millis()),
+            problem.key()
+        )
+    }
+
+    /// The learner's calendar day when they answered.
+    pub(crate) fn date(&self) -> NaiveDate {
+        day_of(self.answered_at, self.utc_offset_mins)
+    }
+
+    /// Whether the answer goes through the lighter slip penalty.
+    pub(crate) fn is_slip(&self) -> bool {
+        self.mistake.is_some_and(|m| m.is_slip())
+    }
+}
+
+/// An answer given away from the server, e.g. offline, to be applied later
+/// with `SpacedRepetition::apply_answers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientAnswer {
+    /// Made by the client, unique per answer. An id that has been applied
+    /// before is skipped.
+    pub id: String,
+    #[serde(flatten)]
+    pub problem: Problem,
+    pub answer: u32,
+    pub response_secs: f64,
+    #[serde(default)]
+    pub mode: AnswerMode,
+    /// When the learner answered, by the client's clock.
+    pub answered_at: DateTime<Utc>,
+    /// Minutes east of UTC on the client's clock. Clients that don't send it
+    /// get the offset given for the whole batch.
+    #[serde(default)]
+    pub utc_offset_mins: Option<i32>,
+}
+
+/// What became of one answer in a batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedAnswer {
+    pub id: String,
+    /// False if the answer had already been applied, or was to a fact
+    /// outside the tables.
+    pub applied: bool,
+    pub correct: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mistake: Option<Mistake>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Journal {
+    /// Oldest first.
+    entries: Vec<JournalEntry>,
+    /// Ids of answers taken back with undo, oldest first. Kept so that
+    /// sending an undone answer again, or merging with a device that still
+    /// has it, doesn't bring it back.
+    undone: Vec<String>,
+}
+
+impl Journal {
+    /// True for answers in the journal and answers that were undone, which
+    /// have both been applied once already.
+    pub(crate) fn contains(&self, id: &str) -> bool {
+        self.entries.iter().rev().any(|e| e.id == id) || self.is_undone(id)
+    }
+
+    /// The ids `contains` is true for.
+    pub(crate) fn ids(&self) -> HashSet<String> {
+        let entries = self.entries.iter().map(|e| &e.id);
+        entries.chain(&self.undone).cloned().collect()
+    }
+
+    pub(crate) fn is_undone(&self, id: &str) -> bool {
+        self.undone.iter().rev().any(|u| u == id)
+    }
+
+    pub(crate) fn push(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.entries.len() > JOURNAL_LIMIT {
+            let excess = self.entries.len() - JOURNAL_LIMIT;
+            self.entries.drain(..excess);
+        }
+        if self.undone.len() > JOURNAL_LIMIT {
+            let excess = self.undone.len() - JOURNAL_LIMIT;
+            self.undone.drain(..excess);
+        }
+    }
+
+    /// Takes the answers with these ids out of the journal and remembers
+    /// them as undone. Returns the entries that were here.
+    pub(crate) fn undo(&mut self, ids: &[String]) -> Vec<JournalEntry> {
+        let (removed, kept) = self.entries.drain(..).partition(|e| ids.contains(&e.id));
+        self.entries = kept;
+        for id in ids {
+            if !self.is_undone(id) {
+                self.undone.push(id.clone());
+            }
+        }
+        self.trim();
+        removed
+    }
+
+    pub(crate) fn undone(&self) -> &[String] {
+        &self.undone
+    }
+
+    pub(crate) fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// The entries whose ids `other` doesn't have.
+    pub(crate) fn missing_from(&self, other: &Journal) -> Vec<&JournalEntry> {
+        let known: HashSet<&str> = other.entries.iter().map(|e| e.id.as_str()).collect();
+        self.entries
+            .iter()
+            .filter(|e| !known.contains(e.id.as_str()))
+            .collect()
+    }
+
+    /// Adds the entries of `other` that aren't here yet, keeping the journal
+    /// in order of when answers were given. Answers undone on either side
+    /// stay out.
+    pub(crate) fn merge(&mut self, other: &Journal) {
+        for id in &other.undone {
+            if !self.is_undone(id) {
+                self.undone.push(id.clone());
+            }
+        }
+        let missing: Vec<JournalEntry> = other
+            .missing_from(self)
+            .into_iter()
+            .filter(|e| !self.is_undone(&e.id))
+            .cloned()
+            .collect();
+        self.entries.extend(missing);
+        self.entries.sort_by(|a, b| {
+            a.answered_at
+                .cmp(&b.answered_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        self.trim();
+    }
+}
+
+/// Answers counted from journal entries, for merging.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Tally {
+    pub correct: u32,
+    pub wrong: u32,
+    pub recognised: u32,
+    pub practice_secs: f64,
+    /// When the latest of the answers was given.
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl Tally {
+    pub(crate) fn add(&mut self, entry: &JournalEntry) {
+        if entry.correct {
+            self.correct += 1;
+            if entry.mode == AnswerMode::MultipleChoice {
+                self.recognised += 1;
+            }
+        } else {
+            self.wrong += 1;
+        }
+        self.practice_secs += entry.response_secs.clamp(0.0, MAX_SECS_PER_ANSWER);
+        self.last = self.last.max(Some(entry.answered_at));
+    }
+
+    /// Tallies per the learner's calendar day, as `DailyProgress` counts them.
+    pub(crate) fn by_day(entries: &[&JournalEntry]) -> BTreeMap<NaiveDate, Tally> {
+        let mut days: BTreeMap<NaiveDate, Tally> = BTreeMap::new();
+        for entry in entries {
+            days.entry(entry.date()).or_default().add(entry);
+        }
+        days
+    }
+}
@@ -1,2 +1,17 @@
+pub mod choice;
+pub mod daily;
+pub mod focus;
+pub mod hint;
+pub mod journal;
+pub mod locale;
+pub mod migrate;
+pub mod parse;
 pub mod problem;
+mod rng;
+pub mod session;
+pub mod slip;
 pub mod spaced_rep;
+pub mod sprint;
+pub mod stats;
+pub mod visual;
+pub mod worksheet;
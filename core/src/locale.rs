@@ -0,0 +1,369 @@
+use crate::problem::Problem;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "fr")]
+    French,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "es")]
+    Spanish,
+    #[serde(rename = "ar")]
+    Arabic,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::English,
+        Language::French,
+        Language::German,
+        Language::Spanish,
+        Language::Arabic,
+    ];
+
+    /// The language's name in itself, for pickers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+            Language::Arabic => "العربية",
+        }
+    }
+
+    pub fn is_rtl(&self) -> bool {
+        matches!(self, Language::Arabic)
+    }
+
+    /// How multiplication is usually written where the language is taught.
+    pub fn default_operator(&self) -> Operator {
+        match self {
+            Language::German => Operator::Dot,
+            _ => Operator::Cross,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// 7 × 8
+    Cross,
+    /// 7 · 8
+    Dot,
+    /// 7 x 8
+    Letter,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 3] = [Operator::Cross, Operator::Dot, Operator::Letter];
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Operator::Cross => '×',
+            Operator::Dot => '·',
+            Operator::Letter => 'x',
+        }
+    }
+}
+
+/// How problems are written for a learner: the language for words and digits,
+/// and the multiplication sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Locale {
+    pub language: Language,
+    pub operator: Operator,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new(Language::default())
+    }
+}
+
+impl Locale {
+    /// The language with its usual multiplication sign.
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            operator: language.default_operator(),
+        }
+    }
+
+    pub fn with_operator(self, operator: Operator) -> Self {
+        Self { operator, ..self }
+    }
+
+    pub fn is_rtl(&self) -> bool {
+        self.language.is_rtl()
+    }
+
+    /// `n` in the language's digits: Arabic uses Arabic-Indic digits.
+    pub fn number(&self, n: u32) -> String {
+        match self.language {
+            Language::Arabic => n
+                .to_string()
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(d) => char::from_u32('٠' as u32 + d).unwrap_or(c),
+                    None => c,
+                })
+                .collect(),
+            _ => n.to_string(),
+        }
+    }
+
+    /// "7 × 8 = ?"
+    pub fn question(&self, problem: &Problem) -> String {
+        let mark = if self.is_rtl() { '؟' } else { '?' };
+        format!("{} = {}", self.product(problem), mark)
+    }
+
+    /// "7 × 8 = 56"
+    pub fn equation(&self, problem: &Problem) -> String {
+        format!(
+            "{} = {}",
+            self.product(problem),
+            self.number(problem.answer())
+        )
+    }
+
+    fn product(&self, problem: &Problem) -> String {
+        format!(
+            "{} {} {}",
+            self.number(problem.a as u32),
+            self.operator.symbol(),
+            self.number(problem.b as u32)
+        )
+    }
+
+    /// The question in words, e.g. "What is seven times eight?".
+    pub fn question_words(&self, problem: &Problem) -> String {
+        let a = self.number_words(problem.a as u32).unwrap_or_default();
+        let b = self.number_words(problem.b as u32).unwrap_or_default();
+        match self.language {
+            Language::English => format!("What is {} times {}?", a, b),
+            Language::French => format!("Combien font {} fois {}\u{a0}?", a, b),
+            Language::German => format!("Was ist {} mal {}?", a, b),
+            Language::Spanish => format!("¿Cuánto es {} por {}?", a, b),
+            Language::Arabic => format!("كم يساوي {} في {}؟", a, b),
+        }
+    }
+
+    /// `n` written out, for 0 to 999; `None` beyond that.
+    pub fn number_words(&self, n: u32) -> Option<String> {
+        if n > 999 {
+            return None;
+        }
+        Some(match self.language {
+            Language::English => english(n),
+            Language::French => french(n),
+            Language::German => german(n),
+            Language::Spanish => spanish(n),
+            Language::Arabic => arabic(n),
+        })
+    }
+}
+
+const EN_ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+const EN_TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+fn english(n: u32) -> String {
+    let below_100 = |n: u32| match (n / 10, n % 10) {
+        (0..=1, _) => EN_ONES[n as usize].to_string(),
+        (tens, 0) => EN_TENS[tens as usize].to_string(),
+        (tens, unit) => format!("{}-{}", EN_TENS[tens as usize], EN_ONES[unit as usize]),
+    };
+    match (n / 100, n % 100) {
+        (0, rest) => below_100(rest),
+        (h, 0) => format!("{} hundred", EN_ONES[h as usize]),
+        (h, r) => format!("{} hundred and {}", EN_ONES[h as usize], below_100(r)),
+    }
+}
+
+const FR_ONES: [&str; 17] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix", "onze",
+    "douze", "treize", "quatorze", "quinze", "seize",
+];
+const FR_TENS: [&str; 7] = [
+    "", "", "vingt", "trente", "quarante", "cinquante", "soixante",
+];
+
+fn french_below_100(n: u32) -> String {
+    match n {
+        0..=16 => FR_ONES[n as usize].to_string(),
+        17..=19 => format!("dix-{}", FR_ONES[(n - 10) as usize]),
+        20..=69 => {
+            let tens = FR_TENS[(n / 10) as usize];
+            match n % 10 {
+                0 => tens.to_string(),
+                1 => format!("{} et un", tens),
+                unit => format!("{}-{}", tens, FR_ONES[unit as usize]),
+            }
+        }
+        71 => "soixante et onze".to_string(),
+        70..=79 => format!("soixante-{}", french_below_100(n - 60)),
+        80 => "quatre-vingts".to_string(),
+        _ => format!("quatre-vingt-{}", french_below_100(n - 80)),
+    }
+}
+
+fn french(n: u32) -> String {
+    match (n / 100, n % 100) {
+        (0, rest) => french_below_100(rest),
+        (1, 0) => "cent".to_string(),
+        (1, r) => format!("cent {}", french_below_100(r)),
+        (h, 0) => format!("{} cents", FR_ONES[h as usize]),
+        (h, r) => format!("{} cent {}", FR_ONES[h as usize], french_below_100(r)),
+    }
+}
+
+const DE_ONES: [&str; 20] = [
+    "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun", "zehn",
+    "elf", "zwölf", "dreizehn", "vierzehn", "fünfzehn", "sechzehn", "siebzehn", "achtzehn",
+    "neunzehn",
+];
+const DE_TENS: [&str; 10] = [
+    "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig",
+    "neunzig",
+];
+
+/// German writes numbers as one word, and "eins" becomes "ein" in front of
+/// another part: einundzwanzig, einhundert.
+fn german(n: u32) -> String {
+    let prefix = |n: u32| if n == 1 { "ein" } else { DE_ONES[n as usize] };
+    let below_100 = |n: u32| match (n / 10, n % 10) {
+        (0..=1, _) => DE_ONES[n as usize].to_string(),
+        (tens, 0) => DE_TENS[tens as usize].to_string(),
+        (tens, unit) => format!("{}und{}", prefix(unit), DE_TENS[tens as usize]),
+    };
+    match (n / 100, n % 100) {
+        (0, rest) => below_100(rest),
+        (h, 0) => format!("{}hundert", prefix(h)),
+        (h, r) => format!("{}hundert{}", prefix(h), below_100(r)),
+    }
+}
+
+const ES_ONES: [&str; 30] = [
+    "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve", "diez",
+    "once", "doce", "trece", "catorce", "quince", "dieciséis", "diecisiete", "dieciocho",
+    "diecinueve", "veinte", "veintiuno", "veintidós", "veintitrés", "veinticuatro",
+    "veinticinco", "veintiséis", "veintisiete", "veintiocho", "veintinueve",
+];
+const ES_TENS: [&str; 10] = [
+    "", "", "veinte", "treinta", "cuarenta", "cincuenta", "sesenta", "setenta", "ochenta",
+    "noventa",
+];
+const ES_HUNDREDS: [&str; 10] = [
+    "", "ciento", "doscientos", "trescientos", "cuatrocientos", "quinientos", "seiscientos",
+    "setecientos", "ochocientos", "novecientos",
+];
+
+fn spanish(n: u32) -> String {
+    let below_100 = |n: u32| match (n / 10, n % 10) {
+        (0..=2, _) => ES_ONES[n as usize].to_string(),
+        (tens, 0) => ES_TENS[tens as usize].to_string(),
+        (tens, unit) => format!("{} y {}", ES_TENS[tens as usize], ES_ONES[unit as usize]),
+    };
+    match (n / 100, n % 100) {
+        (0, rest) => below_100(rest),
+        (1, 0) => "cien".to_string(),
+        (h, 0) => ES_HUNDREDS[h as usize].to_string(),
+        (h, r) => format!("{} {}", ES_HUNDREDS[h as usize], below_100(r)),
+    }
+}
+
+const AR_ONES: [&str; 11] = [
+    "صفر", "واحد", "اثنان", "ثلاثة", "أربعة", "خمسة", "ستة", "سبعة", "ثمانية", "تسعة", "عشرة",
+];
+const AR_TENS: [&str; 10] = [
+    "", "", "عشرون", "ثلاثون", "أربعون", "خمسون", "ستون", "سبعون", "ثمانون", "تسعون",
+];
+const AR_HUNDREDS: [&str; 10] = [
+    "", "مائة", "مئتان", "ثلاثمائة", "أربعمائة", "خمسمائة", "ستمائة", "سبعمائة", "ثمانمائة",
+    "تسعمائة",
+];
+
+/// Arabic puts the units before the tens, joined with "و": ستة وخمسون is
+/// six and fifty.
+fn arabic(n: u32) -> String {
+    let below_100 = |n: u32| match (n / 10, n % 10) {
+        (0, _) | (1, 0) => AR_ONES[n as usize].to_string(),
+        (1, 1) => "أحد عشر".to_string(),
+        (1, 2) => "اثنا عشر".to_string(),
+        (1, unit) => format!("{} عشر", AR_ONES[unit as usize]),
+        (tens, 0) => AR_TENS[tens as usize].to_string(),
+        (tens, unit) => format!("{} و{}", AR_ONES[unit as usize], AR_TENS[tens as usize]),
+    };
+    match (n / 100, n % 100) {
+        (0, rest) => below_100(rest),
+        (h, 0) => AR_HUNDREDS[h as usize].to_string(),
+        (h, r) => format!("{} و{}", AR_HUNDREDS[h as usize], below_100(r)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(language: Language, n: u32) -> String {
+        Locale::new(language).number_words(n).unwrap()
+    }
+
+    #[test]
+    fn numbers_are_written_out_in_each_language() {
+        assert_eq!(words(Language::English, 56), "fifty-six");
+        assert_eq!(words(Language::English, 144), "one hundred and forty-four");
+        assert_eq!(words(Language::French, 71), "soixante et onze");
+        assert_eq!(words(Language::French, 80), "quatre-vingts");
+        assert_eq!(words(Language::French, 96), "quatre-vingt-seize");
+        assert_eq!(words(Language::French, 200), "deux cents");
+        assert_eq!(words(Language::German, 21), "einundzwanzig");
+        assert_eq!(words(Language::German, 144), "einhundertvierundvierzig");
+        assert_eq!(words(Language::Spanish, 22), "veintidós");
+        assert_eq!(words(Language::Spanish, 100), "cien");
+        assert_eq!(words(Language::Spanish, 121), "ciento veintiuno");
+        assert_eq!(words(Language::Arabic, 12), "اثنا عشر");
+        assert_eq!(words(Language::Arabic, 56), "ستة وخمسون");
+        assert_eq!(Locale::default().number_words(1000), None);
+    }
+
+    #[test]
+    fn arabic_is_right_to_left_with_arabic_indic_digits() {
+        let arabic = Locale::new(Language::Arabic);
+        assert!(arabic.is_rtl());
+        assert!(!Locale::new(Language::French).is_rtl());
+        assert_eq!(arabic.number(56), "٥٦");
+        assert_eq!(arabic.question(&Problem::new(7, 8)), "٧ × ٨ = ؟");
+        assert_eq!(arabic.equation(&Problem::new(7, 8)), "٧ × ٨ = ٥٦");
+        assert_eq!(
+            arabic.question_words(&Problem::new(7, 8)),
+            "كم يساوي سبعة في ثمانية؟"
+        );
+    }
+
+    #[test]
+    fn each_language_writes_its_usual_sign_unless_told_otherwise() {
+        let problem = Problem::new(7, 8);
+        assert_eq!(Locale::default().question(&problem), "7 × 8 = ?");
+        let german = Locale::new(Language::German);
+        assert_eq!(german.question(&problem), "7 · 8 = ?");
+        let letter = german.with_operator(Operator::Letter);
+        assert_eq!(letter.equation(&problem), "7 x 8 = 56");
+        assert_eq!(
+            Locale::default().question_words(&problem),
+            "What is seven times eight?"
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Versioned save format for `SpacedRepetition`.
+//!
+//! Every saved document carries a `version` field. Older documents are
+//! upgraded one version at a time by the steps in `MIGRATIONS`, so each step
+//! only has to know about the version directly before it. Documents written
+//! by a newer build are refused rather than loaded with fields dropped.
+
+use crate::daily::DailyProgress;
+use crate::problem::TimeThresholds;
+use crate::sprint::SprintRecords;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// The version written by this build.
+pub const CURRENT_VERSION: u32 = 7;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7,
+];
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The text isn't JSON, or doesn't have the shape of a progress document.
+    Invalid(serde_json::Error),
+    /// The document was saved by a newer version of the app.
+    TooNew { found: u32, supported: u32 },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Invalid(e) => write!(f, "progress data is invalid: {}", e),
+            LoadError::TooNew { found, supported } => write!(
+                f,
+                "progress was saved by a newer version of the app (format {}, this version \
+                 supports up to {}); please update",
+                found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Invalid(e)
+    }
+}
+
+/// Brings a saved document up to `CURRENT_VERSION`.
+pub(crate) fn upgrade(mut value: Value) -> Result<Value, LoadError> {
+    let Some(doc) = value.as_object_mut() else {
+        return Err(LoadError::Invalid(serde::de::Error::custom(
+            "expected a JSON object",
+        )));
+    };
+
+    // Documents from before versioning have no version field.
+    let found = match doc.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| {
+                LoadError::Invalid(serde::de::Error::custom("version is not a number"))
+            })?,
+    };
+    if found > CURRENT_VERSION {
+        return Err(LoadError::TooNew {
+            found,
+            supported: CURRENT_VERSION,
+        });
+    }
+
+    for (version, migrate) in MIGRATIONS.iter().enumerate().skip(found as usize) {
+        migrate(doc);
+        doc.insert("version".to_string(), json!(version + 1));
+    }
+    Ok(value)
+}
+
+/// v0 → v1: the unversioned format. Fills in the fields that older builds
+/// didn't write yet.
+fn v0_to_v1(doc: &mut Map<String, Value>) {
+    doc.entry("unlocked_tables").or_insert(json!(1));
+
+    if let Some(Value::Object(stats)) = doc.get_mut("stats") {
+        for stat in stats.values_mut() {
+            let Some(stat) = stat.as_object_mut() else {
+                continue;
+            };
+            if !stat.contains_key("best_tier") {
+                // Backfill the tier that the stats already earned, rather than
+                // showing a practised fact as "not started".
+                let get = |k: &str| stat.get(k).and_then(Value::as_f64).unwrap_or(0.0);
+                let tier = if get("consecutive_correct") >= 3.0 && get("ease_factor") >= 2.0 {
+                    2
+                } else if get("times_correct") > 0.0 {
+                    1
+                } else {
+                    0
+                };
+                stat.insert("best_tier".to_string(), json!(tier));
+            }
+            stat.entry("consecutive_fast_correct").or_insert(json!(0));
+        }
+    }
+
+    doc.entry("sprints")
+        .or_insert_with(|| serde_json::to_value(SprintRecords::default()).unwrap_or_default());
+    doc.entry("daily")
+        .or_insert_with(|| serde_json::to_value(DailyProgress::default()).unwrap_or_default());
+}
+
+/// v1 → v2: adds the commutative pairs setting, off by default.
+fn v1_to_v2(doc: &mut Map<String, Value>) {
+    doc.entry("commutative").or_insert(json!(false));
+}
+
+/// v2 → v3: counts multiple-choice answers separately.
+fn v2_to_v3(doc: &mut Map<String, Value>) {
+    if let Some(Value::Object(stats)) = doc.get_mut("stats") {
+        for stat in stats.values_mut().filter_map(Value::as_object_mut) {
+            stat.entry("times_recognised").or_insert(json!(0));
+        }
+    }
+}
+
+/// v3 → v4: adds the answer journal. Earlier answers weren't journaled, so it
+/// starts empty. A pending undo from v3 has no journal entry to go with it and
+/// is dropped.
+fn v3_to_v4(doc: &mut Map<String, Value>) {
+    doc.entry("journal")
+        .or_insert(json!({ "entries": [], "undone": [] }));
+    doc.remove("undo");
+}
+
+/// v4 → v5: keeps when progress was last reset and when the settings were
+/// last changed, so merging can tell which side is newer. Neither was kept
+/// before, so both are unknown.
+fn v4_to_v5(doc: &mut Map<String, Value>) {
+    doc.entry("reset_at").or_insert(Value::Null);
+    doc.entry("settings_changed_at").or_insert(Value::Null);
+}
+
+/// v5 → v6: keeps how many facts were mastered on each day. Earlier days
+/// weren't counted, so they have none, and neither does the day kept for a
+/// pending undo.
+fn v5_to_v6(doc: &mut Map<String, Value>) {
+    if let Some(Value::Object(days)) = doc.get_mut("daily").and_then(|d| d.get_mut("days")) {
+        for day in days.values_mut().filter_map(Value::as_object_mut) {
+            day.entry("mastered").or_insert(Value::Null);
+        }
+    }
+    let undo_day = doc
+        .get_mut("undo")
+        .and_then(|u| u.get_mut("day"))
+        .and_then(|d| d.get_mut("activity"))
+        .and_then(Value::as_object_mut);
+    if let Some(day) = undo_day {
+        day.entry("mastered").or_insert(Value::Null);
+    }
+}
+
+/// v6 → v7: makes the fast and slow answer times a setting, starting at the
+/// times that were fixed before.
+fn v6_to_v7(doc: &mut Map<String, Value>) {
+    doc.entry("time_thresholds").or_insert_with(|| {
+        serde_json::to_value(TimeThresholds::default()).unwrap_or_default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::problem::{Problem, TimeThresholds};
+    use crate::spaced_rep::SpacedRepetition;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_documents_upgrade_to_the_current_version() {
+        let v0 = json!({
+            "stats": {
+                "7x8": {
+                    "problem": { "a": 7, "b": 8 },
+                    "ease_factor": 2.5,
+                    "interval_days": 6.0,
+                    "next_review": "2026-01-10T09:00:00Z",
+                    "times_correct": 4,
+                    "times_wrong": 1,
+                    "consecutive_correct": 3
+                },
+                "2x3": {
+                    "problem": { "a": 2, "b": 3 },
+                    "ease_factor": 2.3,
+                    "interval_days": 0.0,
+                    "next_review": "2026-01-10T09:00:00Z",
+                    "times_correct": 0,
+                    "times_wrong": 2,
+                    "consecutive_correct": 0
+                }
+            }
+        });
+        let upgraded = super::upgrade(v0).unwrap();
+        assert_eq!(upgraded["version"], json!(super::CURRENT_VERSION));
+        // Backfilled from the stats rather than left at "not started".
+        assert_eq!(upgraded["stats"]["7x8"]["best_tier"], json!(2));
+        assert_eq!(upgraded["stats"]["2x3"]["best_tier"], json!(0));
+
+        let sr: SpacedRepetition = serde_json::from_value(upgraded).unwrap();
+        assert_eq!((sr.total_correct(), sr.total_wrong()), (4, 3));
+        assert_eq!(sr.next_table_to_unlock(), Some(10));
+        assert!(!sr.is_commutative());
+        assert_eq!(sr.stats_for(&Problem::new(7, 8)).unwrap().times_recognised, 0);
+        assert!(sr.journal().is_empty());
+        assert_eq!(sr.time_thresholds(), TimeThresholds::default());
+    }
+
+    #[test]
+    fn days_from_version_5_still_load() {
+        let mut sr = SpacedRepetition::new();
+        // The second answer's undo keeps the day as the first one left it.
+        sr.record_answer(&Problem::new(1, 1), true, 2.0);
+        sr.record_answer(&Problem::new(1, 2), true, 2.0);
+        let mut v5 = serde_json::to_value(&sr).unwrap();
+        v5["version"] = json!(5);
+        let days = v5["daily"]["days"].as_object_mut().unwrap();
+        for day in days.values_mut() {
+            day.as_object_mut().unwrap().remove("mastered");
+        }
+        v5["undo"]["day"]["activity"]
+            .as_object_mut()
+            .unwrap()
+            .remove("mastered");
+
+        let loaded = SpacedRepetition::from_json(&v5.to_string()).unwrap();
+        let (_, day) = loaded.daily().calendar().next().unwrap();
+        assert_eq!((day.correct, day.mastered), (2, None));
+        assert!(loaded.can_undo());
+    }
+
+    #[test]
+    fn documents_from_a_newer_version_are_refused() {
+        let newer = json!({ "version": super::CURRENT_VERSION + 1 });
+        match SpacedRepetition::from_json(&newer.to_string()) {
+            Err(super::LoadError::TooNew { found, supported }) => {
+                assert_eq!(found, super::CURRENT_VERSION + 1);
+                assert_eq!(supported, super::CURRENT_VERSION);
+            }
+            other => panic!("expected TooNew, got {:?}", other.map(|_| ())),
+        }
+    }
+}
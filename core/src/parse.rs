@@ -0,0 +1,195 @@
+//! Reading a typed answer.
+//!
+//! Learners type on all sorts of keyboards, so digits from other scripts are
+//! read as digits, spaces anywhere are ignored, and thousands separators are
+//! allowed when they group digits in threes. Input that still isn't a number
+//! is reported instead of being thrown away, so the frontends can say so and
+//! leave the text for the learner to fix.
+
+use crate::locale::{Language, Locale};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    Empty,
+    /// The text isn't a number, or is too large to be one.
+    NotUnderstood(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Type an answer first"),
+            ParseError::NotUnderstood(input) => {
+                write!(
+                    f,
+                    "Couldn't understand \"{}\". Type a number like 56",
+                    input
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The zero of each run of ten decimal digits that is read: ASCII,
+/// Arabic-Indic, Extended Arabic-Indic, Devanagari, Bengali, Gurmukhi,
+/// Gujarati, Oriya, Tamil, Telugu, Kannada, Malayalam, Thai, Lao, Tibetan,
+/// Myanmar, Khmer, Mongolian and full-width.
+const DIGIT_ZEROS: [u32; 19] = [
+    0x30, 0x660, 0x6F0, 0x966, 0x9E6, 0xA66, 0xAE6, 0xB66, 0xBE6, 0xC66, 0xCE6, 0xD66, 0xE50,
+    0xED0, 0xF20, 0x1040, 0x17E0, 0x1810, 0xFF10,
+];
+
+/// Characters used to group thousands: 1,000  1.000  1'000  1٬000.
+const GROUP_SEPARATORS: [char; 6] = [',', '.', '\'', '’', '٬', '，'];
+
+/// Words joining the parts of a number that can be left out or put in:
+/// "one hundred forty" and "one hundred and forty" are both fine.
+fn connectors(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::English => &["and"],
+        Language::French => &["et"],
+        Language::Spanish => &["y"],
+        Language::German | Language::Arabic => &[],
+    }
+}
+
+fn digit_value(c: char) -> Option<u32> {
+    DIGIT_ZEROS.iter().find_map(|&zero| {
+        let offset = (c as u32).checked_sub(zero)?;
+        (offset < 10).then_some(offset)
+    })
+}
+
+/// Reads an answer typed as digits or, when `words` is given, written out in
+/// that language ("fifty six", "cinquante-six").
+pub fn parse_answer(input: &str, words: Option<Language>) -> Result<u32, ParseError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let not_understood = || ParseError::NotUnderstood(trimmed.to_string());
+
+    if let Some(n) = parse_digits(trimmed) {
+        return Ok(n);
+    }
+    words
+        .and_then(|language| parse_words(trimmed, language))
+        .ok_or_else(not_understood)
+}
+
+fn parse_digits(input: &str) -> Option<u32> {
+    // Split into runs of digits at the separators, ignoring whitespace.
+    let mut groups: Vec<String> = vec![String::new()];
+    for c in input.chars().filter(|c| !c.is_whitespace()) {
+        if let Some(d) = digit_value(c) {
+            groups.last_mut()?.push(char::from_digit(d, 10)?);
+        } else if GROUP_SEPARATORS.contains(&c) {
+            groups.push(String::new());
+        } else {
+            return None;
+        }
+    }
+
+    // "1,000" is a thousand, but "5,6" and "5.6" aren't whole numbers.
+    let (first, rest) = groups.split_first()?;
+    if first.is_empty() || rest.iter().any(|g| g.len() != 3) {
+        return None;
+    }
+    groups.concat().parse().ok()
+}
+
+/// Compares the input with every number the locale can write out. Case,
+/// hyphens, spacing, accents and connecting words don't matter.
+fn parse_words(input: &str, language: Language) -> Option<u32> {
+    let target = normalise_words(input, language);
+    let locale = Locale::new(language);
+    (0..=999).find(|&n| {
+        locale
+            .number_words(n)
+            .is_some_and(|w| normalise_words(&w, language) == target)
+    })
+}
+
+fn normalise_words(text: &str, language: Language) -> String {
+    let lower = text.to_lowercase();
+    lower
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|w| !w.is_empty() && !connectors(language).contains(w))
+        .flat_map(|w| w.chars())
+        .map(fold_accent)
+        .collect::<String>()
+        .replace('ß', "ss")
+}
+
+/// Accents young learners tend to leave off.
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'â' | 'á' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'î' | 'ï' => 'i',
+        'ó' | 'ô' | 'ö' => 'o',
+        'ú' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn not_understood(input: &str) -> Result<u32, ParseError> {
+        Err(ParseError::NotUnderstood(input.to_string()))
+    }
+
+    #[test]
+    fn digits_from_any_script_are_read() {
+        assert_eq!(parse_answer("56", None), Ok(56));
+        assert_eq!(parse_answer(" 5 6 ", None), Ok(56));
+        assert_eq!(parse_answer("٥٦", None), Ok(56));
+        assert_eq!(parse_answer("۵۶", None), Ok(56));
+        assert_eq!(parse_answer("५६", None), Ok(56));
+        assert_eq!(parse_answer("５６", None), Ok(56));
+    }
+
+    #[test]
+    fn separators_only_group_thousands() {
+        assert_eq!(parse_answer("1,000", None), Ok(1000));
+        assert_eq!(parse_answer("1.000", None), Ok(1000));
+        assert_eq!(parse_answer("1 000", None), Ok(1000));
+        assert_eq!(parse_answer("5.6", None), not_understood("5.6"));
+        assert_eq!(parse_answer("5,6", None), not_understood("5,6"));
+        assert_eq!(parse_answer(",56", None), not_understood(",56"));
+    }
+
+    #[test]
+    fn number_words_are_read_in_the_given_language() {
+        assert_eq!(parse_answer("fifty six", Some(Language::English)), Ok(56));
+        assert_eq!(parse_answer("Fifty-Six", Some(Language::English)), Ok(56));
+        assert_eq!(
+            parse_answer("one hundred and forty four", Some(Language::English)),
+            Ok(144)
+        );
+        assert_eq!(
+            parse_answer("cinquante-six", Some(Language::French)),
+            Ok(56)
+        );
+        assert_eq!(parse_answer("fifty six", None), not_understood("fifty six"));
+    }
+
+    #[test]
+    fn anything_else_is_reported() {
+        assert_eq!(parse_answer("   ", None), Err(ParseError::Empty));
+        assert_eq!(
+            parse_answer(" 5a ", Some(Language::English)),
+            not_understood("5a")
+        );
+        assert_eq!(
+            parse_answer("99999999999", None),
+            not_understood("99999999999")
+        );
+    }
+}
@@ -1,8 +1,52 @@
+use crate::choice::AnswerMode;
+use crate::journal::Tally;
+use crate::locale::Locale;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 pub const TABLE_ORDER: [u8; 12] = [1, 10, 5, 11, 2, 3, 9, 4, 6, 7, 8, 12];
 
+/// Number of facts, 1×1 through 12×12.
+pub const PROBLEM_COUNT: usize = 144;
+
+/// Longest review interval. Without a cap, repeated extra practice on a known
+/// fact grows the interval until the review date overflows.
+const MAX_INTERVAL_DAYS: f64 = 365.0;
+
+/// How quickly a typed answer has to come to count as fast, and after how
+/// long it counts as slow. Fast answers raise the ease most and lead to the
+/// fast and mastered tiers; slow ones raise it least.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeThresholds {
+    pub fast_secs: f64,
+    pub slow_secs: f64,
+}
+
+impl Default for TimeThresholds {
+    fn default() -> Self {
+        Self {
+            fast_secs: 3.0,
+            slow_secs: 8.0,
+        }
+    }
+}
+
+impl TimeThresholds {
+    pub const MIN_FAST_SECS: f64 = 1.0;
+    pub const MAX_SLOW_SECS: f64 = 60.0;
+
+    /// Keeps both within range, with slow no sooner than fast.
+    pub fn clamped(self) -> Self {
+        let fast_secs = self
+            .fast_secs
+            .clamp(Self::MIN_FAST_SECS, Self::MAX_SLOW_SECS);
+        Self {
+            fast_secs,
+            slow_secs: self.slow_secs.clamp(fast_secs, Self::MAX_SLOW_SECS),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Problem {
     pub a: u8,
@@ -18,14 +62,42 @@ impl Problem {
         self.a as u32 * self.b as u32
     }
 
+    /// The question in the default locale; see `Locale::question` for others.
     pub fn display(&self) -> String {
-        format!("{} × {} = ?", self.a, self.b)
+        Locale::default().question(self)
     }
 
     pub fn key(&self) -> String {
         format!("{}x{}", self.a, self.b)
     }
 
+    /// Parses a key such as "7x8". Also accepts "7 × 8", "7*8" and "7X8".
+    pub fn from_key(key: &str) -> Option<Self> {
+        let (a, b) = key.split_once(['x', 'X', '×', '*'])?;
+        let a: u8 = a.trim().parse().ok()?;
+        let b: u8 = b.trim().parse().ok()?;
+        if (1..=12).contains(&a) && (1..=12).contains(&b) {
+            Some(Self::new(a, b))
+        } else {
+            None
+        }
+    }
+
+    /// Dense index in 0..PROBLEM_COUNT, in the same a-major order as
+    /// `generate_all_problems`. `None` for factors outside 1..=12.
+    pub fn index(&self) -> Option<usize> {
+        if (1..=12).contains(&self.a) && (1..=12).contains(&self.b) {
+            Some((self.a as usize - 1) * 12 + (self.b as usize - 1))
+        } else {
+            None
+        }
+    }
+
+    /// The same fact with the factors swapped: 7×3 for 3×7.
+    pub fn commuted(&self) -> Self {
+        Self::new(self.b, self.a)
+    }
+
     pub fn tables_required(&self) -> (u8, u8) {
         (self.a, self.b)
     }
@@ -41,11 +113,11 @@ pub struct ProblemStats {
     pub times_wrong: u32,
     pub consecutive_correct: u32,
     /// Achievement tier: 0=not started, 1=learning, 2=solid, 3=fast, 4=mastered. Never reverts.
-    #[serde(default)]
     pub best_tier: u8,
-    /// Consecutive fast (< 3s) correct answers for the current streak.
-    #[serde(default)]
+    /// Consecutive fast (< 3s by default) correct answers for the current streak.
     pub consecutive_fast_correct: u32,
+    /// How many of `times_correct` were picked from multiple choice rather than typed.
+    pub times_recognised: u32,
 }
 
 impl ProblemStats {
@@ -60,57 +132,150 @@ impl ProblemStats {
             consecutive_correct: 0,
             best_tier: 0,
             consecutive_fast_correct: 0,
+            times_recognised: 0,
         }
     }
 
     pub fn is_due(&self) -> bool {
-        Utc::now() >= self.next_review
+        self.is_due_at(Utc::now())
+    }
+
+    pub fn is_due_at(&self, now: DateTime<Utc>) -> bool {
+        now >= self.next_review
     }
 
     pub fn is_mastered(&self) -> bool {
         self.consecutive_correct >= 3 && self.ease_factor >= 2.0
     }
 
+    /// Takes over another fact's review schedule, for facts that are linked
+    /// and should be remembered together. Answer counts stay as they are.
+    pub(crate) fn adopt_schedule(&mut self, other: &ProblemStats) {
+        self.ease_factor = other.ease_factor;
+        self.interval_days = other.interval_days;
+        self.next_review = other.next_review;
+        self.consecutive_correct = other.consecutive_correct;
+        self.consecutive_fast_correct = other.consecutive_fast_correct;
+        self.best_tier = self.best_tier.max(other.best_tier);
+    }
+
+    /// Combines this fact's record with the same fact's record from another
+    /// device; see `SpacedRepetition::merge`. Counts add up the answers only
+    /// one side has, and never drop below either side's; the schedule comes
+    /// from the more recently practised side.
+    pub(crate) fn merge(
+        &mut self,
+        other: &ProblemStats,
+        only_mine: &Tally,
+        only_theirs: &Tally,
+        theirs_is_newer: bool,
+    ) {
+        self.times_correct =
+            (self.times_correct + only_theirs.correct).max(other.times_correct + only_mine.correct);
+        self.times_wrong =
+            (self.times_wrong + only_theirs.wrong).max(other.times_wrong + only_mine.wrong);
+        self.times_recognised = (self.times_recognised + only_theirs.recognised)
+            .max(other.times_recognised + only_mine.recognised);
+        if theirs_is_newer {
+            self.adopt_schedule(other);
+        }
+        self.best_tier = self.best_tier.max(other.best_tier);
+    }
+
+    /// Takes undone answers back out of the counts. The schedule can't be
+    /// rewound from a tally, so it stays as it is.
+    pub(crate) fn forget(&mut self, answers: &Tally) {
+        self.times_correct = self.times_correct.saturating_sub(answers.correct);
+        self.times_wrong = self.times_wrong.saturating_sub(answers.wrong);
+        self.times_recognised = self.times_recognised.saturating_sub(answers.recognised);
+    }
+
     pub fn record_answer(&mut self, correct: bool, response_secs: f64) {
-        let is_fast = response_secs < 3.0;
+        self.record_answer_with_mode(correct, response_secs, AnswerMode::Typed);
+    }
+
+    /// Records an answer given in `mode`. A correct multiple-choice answer
+    /// counts for roughly half a typed one: it moves the schedule and ease
+    /// half as far, and doesn't count toward the fast tiers.
+    pub fn record_answer_with_mode(&mut self, correct: bool, response_secs: f64, mode: AnswerMode) {
+        self.record_answer_at(
+            correct,
+            response_secs,
+            mode,
+            Utc::now(),
+            TimeThresholds::default(),
+        );
+    }
+
+    /// Records an answer given at `at`, which schedules the next review from
+    /// then rather than from now, judging its speed by `thresholds`.
+    pub fn record_answer_at(
+        &mut self,
+        correct: bool,
+        response_secs: f64,
+        mode: AnswerMode,
+        at: DateTime<Utc>,
+        thresholds: TimeThresholds,
+    ) {
+        let recalled = mode == AnswerMode::Typed;
+        let is_fast = response_secs < thresholds.fast_secs;
 
         if correct {
             self.times_correct += 1;
-            let increment = if self.problem.a == 1 || self.problem.b == 1 {
+            if !recalled {
+                self.times_recognised += 1;
+            }
+            let increment: u32 = if self.problem.a == 1 || self.problem.b == 1 {
                 6
             } else if self.problem.a == 10 || self.problem.b == 10 {
                 3
             } else {
                 1
             };
-            self.consecutive_correct += increment;
+            self.consecutive_correct += if recalled {
+                increment
+            } else {
+                increment.div_ceil(2)
+            };
 
-            if self.interval_days == 0.0 {
-                self.interval_days = 1.0;
-            } else if self.interval_days < 1.0 {
-                self.interval_days = 1.0;
+            if self.interval_days < 1.0 {
+                // Two recognised answers make up the first full day.
+                self.interval_days = if recalled {
+                    1.0
+                } else {
+                    (self.interval_days + 0.5).min(1.0)
+                };
             } else {
-                self.interval_days *= self.ease_factor;
+                let growth = if recalled {
+                    self.ease_factor
+                } else {
+                    1.0 + (self.ease_factor - 1.0) / 2.0
+                };
+                self.interval_days = (self.interval_days * growth).min(MAX_INTERVAL_DAYS);
             }
 
             // Adjust ease factor based on response time
-            // Fast (< 3s): +0.15, Normal (3-8s): +0.1, Slow (> 8s): +0.05
+            // Fast (< 3s by default): +0.15, Normal (3-8s): +0.1, Slow (> 8s): +0.05
             let ease_bonus = if is_fast {
                 0.15
-            } else if response_secs <= 8.0 {
+            } else if response_secs <= thresholds.slow_secs {
                 0.1
             } else {
                 0.05
             };
-            self.ease_factor += ease_bonus;
+            self.ease_factor += if recalled { ease_bonus } else { ease_bonus / 2.0 };
             if self.ease_factor > 3.0 {
                 self.ease_factor = 3.0;
             }
 
-            if is_fast {
-                self.consecutive_fast_correct += 1;
-            } else {
-                self.consecutive_fast_correct = 0;
+            // Picking quickly from a few options says little about recall
+            // speed, so recognition leaves the fast streak where it was.
+            if recalled {
+                if is_fast {
+                    self.consecutive_fast_correct += 1;
+                } else {
+                    self.consecutive_fast_correct = 0;
+                }
             }
         } else {
             self.times_wrong += 1;
@@ -123,8 +288,7 @@ impl ProblemStats {
             self.consecutive_fast_correct = 0;
         }
 
-        self.next_review = Utc::now()
-            + chrono::Duration::seconds((self.interval_days * 86400.0) as i64);
+        self.next_review = at + chrono::Duration::seconds((self.interval_days * 86400.0) as i64);
 
         // Advance achievement tier — never reverts.
         // 1=learning, 2=solid, 3=fast, 4=mastered
@@ -141,6 +305,22 @@ impl ProblemStats {
             self.best_tier = self.best_tier.max(4);
         }
     }
+
+    /// Records a wrong answer that was most likely a keyboard slip (see
+    /// `slip`). It still counts as wrong, but the interval is halved rather
+    /// than reset, the streak toward mastery is kept, and the ease drops by
+    /// 0.05 instead of 0.2.
+    pub fn record_slip(&mut self) {
+        self.record_slip_at(Utc::now());
+    }
+
+    pub fn record_slip_at(&mut self, at: DateTime<Utc>) {
+        self.times_wrong += 1;
+        self.interval_days /= 2.0;
+        self.ease_factor = (self.ease_factor - 0.05).max(1.3);
+        self.consecutive_fast_correct = 0;
+        self.next_review = at + chrono::Duration::seconds((self.interval_days * 86400.0) as i64);
+    }
 }
 
 pub fn generate_all_problems() -> Vec<Problem> {
@@ -152,3 +332,20 @@ pub fn generate_all_problems() -> Vec<Problem> {
     }
     problems
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn right_answers_stop_growing_the_interval_at_the_cap() {
+        let mut stats = ProblemStats::new(Problem::new(7, 8));
+        // Uncapped, the interval overflows the review date and panics long
+        // before this many answers.
+        for _ in 0..1000 {
+            stats.record_answer(true, 2.0);
+        }
+        assert_eq!(stats.interval_days, MAX_INTERVAL_DAYS);
+        assert!(stats.next_review <= Utc::now() + chrono::Duration::days(366));
+    }
+}
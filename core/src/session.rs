@@ -0,0 +1,207 @@
+use crate::choice::AnswerMode;
+use crate::problem::Problem;
+use crate::slip::{self, Mistake};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A single answered problem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerRecord {
+    pub problem: Problem,
+    pub answer: u32,
+    pub correct: bool,
+    pub response_secs: f64,
+    pub answered_at: DateTime<Utc>,
+    #[serde(default)]
+    pub mode: AnswerMode,
+    /// Why a wrong answer was wrong: a likely slip, or a fact not yet known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mistake: Option<Mistake>,
+}
+
+/// One sitting of practice: what was answered, and the streak and tallies
+/// shown while practising. Frontends own one of these instead of keeping
+/// their own counters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub answers: Vec<AnswerRecord>,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub correct: u32,
+    pub wrong: u32,
+}
+
+impl Session {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            started_at: Utc::now(),
+            ended_at: None,
+            answers: Vec::new(),
+            streak: 0,
+            best_streak: 0,
+            correct: 0,
+            wrong: 0,
+        }
+    }
+
+    pub fn record(&mut self, problem: Problem, answer: u32, correct: bool, response_secs: f64) {
+        self.record_with_mode(problem, answer, correct, response_secs, AnswerMode::Typed);
+    }
+
+    pub fn record_with_mode(
+        &mut self,
+        problem: Problem,
+        answer: u32,
+        correct: bool,
+        response_secs: f64,
+        mode: AnswerMode,
+    ) {
+        self.record_at(problem, answer, correct, response_secs, mode, Utc::now());
+    }
+
+    /// Records an answer given at `at`, e.g. one replayed after being given
+    /// offline.
+    pub fn record_at(
+        &mut self,
+        problem: Problem,
+        answer: u32,
+        correct: bool,
+        response_secs: f64,
+        mode: AnswerMode,
+        at: DateTime<Utc>,
+    ) {
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.wrong += 1;
+            self.streak = 0;
+        }
+        self.answers.push(AnswerRecord {
+            problem,
+            answer,
+            correct,
+            response_secs,
+            answered_at: at,
+            mode,
+            mistake: if correct {
+                None
+            } else {
+                slip::classify(&problem, answer, mode)
+            },
+        });
+    }
+
+    /// Takes back the last answer, e.g. a typo, and recounts the streaks
+    /// without it.
+    pub fn undo_last(&mut self) -> Option<AnswerRecord> {
+        let record = self.answers.pop()?;
+        if record.correct {
+            self.correct = self.correct.saturating_sub(1);
+        } else {
+            self.wrong = self.wrong.saturating_sub(1);
+        }
+        let (streak, best_streak) = self.answers.iter().fold((0, 0), |(run, best), a| {
+            let run = if a.correct { run + 1 } else { 0 };
+            (run, best.max(run))
+        });
+        self.streak = streak;
+        self.best_streak = best_streak;
+        Some(record)
+    }
+
+    pub fn end(&mut self, at: DateTime<Utc>) {
+        if self.ended_at.is_none() {
+            self.ended_at = Some(at);
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ended_at.is_some()
+    }
+
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        self.answers.last().map_or(self.started_at, |a| a.answered_at)
+    }
+
+    /// True if nothing has been answered for `timeout`. Callers use this to
+    /// close sessions that were abandoned without being ended.
+    pub fn is_idle(&self, now: DateTime<Utc>, timeout: Duration) -> bool {
+        now - self.last_activity() > timeout
+    }
+
+    pub fn answered(&self) -> u32 {
+        self.correct + self.wrong
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.answered() == 0 {
+            0.0
+        } else {
+            self.correct as f64 / self.answered() as f64
+        }
+    }
+
+    pub fn avg_response_secs(&self) -> f64 {
+        if self.answers.is_empty() {
+            0.0
+        } else {
+            self.answers.iter().map(|a| a.response_secs).sum::<f64>() / self.answers.len() as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaks_and_tallies_follow_the_answers() {
+        let mut session = Session::new("s");
+        for correct in [true, true, true, false, true] {
+            let answer = if correct { 56 } else { 54 };
+            session.record(Problem::new(7, 8), answer, correct, 2.0);
+        }
+        assert_eq!((session.correct, session.wrong), (4, 1));
+        assert_eq!((session.streak, session.best_streak), (1, 3));
+        assert_eq!(session.accuracy(), 0.8);
+        assert_eq!(session.avg_response_secs(), 2.0);
+    }
+
+    #[test]
+    fn undoing_an_answer_recounts_the_streaks_without_it() {
+        let mut session = Session::new("s");
+        session.record(Problem::new(3, 4), 12, true, 2.0);
+        session.record(Problem::new(3, 4), 12, true, 2.0);
+        session.record(Problem::new(6, 7), 43, false, 2.0);
+        assert_eq!(session.streak, 0);
+
+        let undone = session.undo_last().unwrap();
+        assert_eq!(undone.answer, 43);
+        assert_eq!((session.correct, session.wrong), (2, 0));
+        assert_eq!((session.streak, session.best_streak), (2, 2));
+        session.undo_last();
+        session.undo_last();
+        assert!(session.undo_last().is_none());
+        assert_eq!((session.streak, session.best_streak), (0, 0));
+    }
+
+    #[test]
+    fn a_session_goes_idle_after_the_last_answer() {
+        let mut session = Session::new("s");
+        let at = session.started_at + Duration::minutes(10);
+        session.record_at(Problem::new(2, 2), 4, true, 2.0, AnswerMode::Typed, at);
+        let timeout = Duration::minutes(30);
+        assert!(!session.is_idle(at + Duration::minutes(29), timeout));
+        assert!(session.is_idle(at + Duration::minutes(31), timeout));
+
+        session.end(at);
+        session.end(at + Duration::hours(1));
+        assert_eq!(session.ended_at, Some(at));
+    }
+}
@@ -0,0 +1,154 @@
+//! Telling keyboard slips from real mistakes.
+//!
+//! A typed 65 for 7×8 most likely means the learner knew 56 and mistyped it,
+//! so it shouldn't send the fact back to the start of its schedule. Answers
+//! that are themselves a likely mistake, such as a neighbouring multiple, are
+//! always treated as knowledge errors even if they also look like a slip.
+
+use crate::choice::AnswerMode;
+use crate::problem::Problem;
+use serde::{Deserialize, Serialize};
+
+/// Why an answer was wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mistake {
+    /// 65 for 56.
+    SwappedDigits,
+    /// 566 for 56: a key registered twice.
+    RepeatedDigit,
+    /// 5 for 56: a key didn't register.
+    DroppedDigit,
+    /// The fact isn't known yet.
+    Knowledge,
+}
+
+impl Mistake {
+    pub fn is_slip(&self) -> bool {
+        !matches!(self, Mistake::Knowledge)
+    }
+
+    /// A short note for feedback, e.g. "Looks like a slip: the digits are swapped".
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Mistake::SwappedDigits => "Looks like a slip: the digits are swapped",
+            Mistake::RepeatedDigit => "Looks like a slip: a digit was typed twice",
+            Mistake::DroppedDigit => "Looks like a slip: a digit is missing",
+            Mistake::Knowledge => "This fact needs more practice",
+        }
+    }
+}
+
+/// Classifies `answer` to `problem`; `None` if it is correct. Picked answers
+/// can't be mistyped, so a wrong multiple-choice answer is always a knowledge
+/// error.
+pub fn classify(problem: &Problem, answer: u32, mode: AnswerMode) -> Option<Mistake> {
+    let correct = problem.answer();
+    if answer == correct {
+        return None;
+    }
+    if mode == AnswerMode::MultipleChoice || is_likely_mistake(problem, answer) {
+        return Some(Mistake::Knowledge);
+    }
+
+    let typed: Vec<char> = answer.to_string().chars().collect();
+    let wanted: Vec<char> = correct.to_string().chars().collect();
+    let slip = if is_adjacent_swap(&typed, &wanted) {
+        Mistake::SwappedDigits
+    } else if is_repeated_digit(&typed, &wanted) {
+        Mistake::RepeatedDigit
+    } else if is_one_removed(&wanted, &typed) {
+        Mistake::DroppedDigit
+    } else {
+        Mistake::Knowledge
+    };
+    Some(slip)
+}
+
+/// A neighbouring multiple in either table, or the sum instead of the
+/// product: answers that come from not knowing the fact.
+fn is_likely_mistake(problem: &Problem, answer: u32) -> bool {
+    let (a, b) = (problem.a as u32, problem.b as u32);
+    [
+        Some(a * (b + 1)),
+        b.checked_sub(1).map(|b| a * b),
+        Some((a + 1) * b),
+        a.checked_sub(1).map(|a| a * b),
+        Some(a + b),
+    ]
+    .contains(&Some(answer))
+}
+
+/// Two neighbouring digits swapped, and nothing else changed.
+fn is_adjacent_swap(typed: &[char], wanted: &[char]) -> bool {
+    typed.len() == wanted.len()
+        && (1..wanted.len()).any(|i| {
+            let mut swapped = wanted.to_vec();
+            swapped.swap(i - 1, i);
+            swapped != wanted && swapped == typed
+        })
+}
+
+/// One digit typed twice in a row: `typed` is `wanted` with a copy of one of
+/// its digits next to it.
+fn is_repeated_digit(typed: &[char], wanted: &[char]) -> bool {
+    typed.len() == wanted.len() + 1
+        && (1..typed.len())
+            .any(|i| typed[i] == typed[i - 1] && [&typed[..i], &typed[i + 1..]].concat() == wanted)
+}
+
+/// `shorter` is `longer` with one digit left out.
+fn is_one_removed(longer: &[char], shorter: &[char]) -> bool {
+    !shorter.is_empty()
+        && longer.len() == shorter.len() + 1
+        && (0..longer.len()).any(|i| [&longer[..i], &longer[i + 1..]].concat() == shorter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(a: u8, b: u8, answer: u32) -> Option<Mistake> {
+        classify(&Problem::new(a, b), answer, AnswerMode::Typed)
+    }
+
+    #[test]
+    fn the_right_answer_is_no_mistake() {
+        assert_eq!(typed(7, 8, 56), None);
+        assert_eq!(
+            classify(&Problem::new(7, 8), 56, AnswerMode::MultipleChoice),
+            None
+        );
+    }
+
+    #[test]
+    fn typing_slips_are_told_apart() {
+        assert_eq!(typed(7, 8, 65), Some(Mistake::SwappedDigits));
+        assert_eq!(typed(7, 8, 566), Some(Mistake::RepeatedDigit));
+        assert_eq!(typed(7, 8, 556), Some(Mistake::RepeatedDigit));
+        assert_eq!(typed(7, 8, 5), Some(Mistake::DroppedDigit));
+        assert_eq!(typed(7, 8, 6), Some(Mistake::DroppedDigit));
+        assert!(typed(7, 8, 65).is_some_and(|m| m.is_slip()));
+    }
+
+    #[test]
+    fn likely_mistakes_are_knowledge_errors() {
+        // Neighbouring multiples and the sum.
+        assert_eq!(typed(7, 8, 63), Some(Mistake::Knowledge));
+        assert_eq!(typed(7, 8, 48), Some(Mistake::Knowledge));
+        assert_eq!(typed(7, 8, 15), Some(Mistake::Knowledge));
+        // 54 is both 45 with its digits swapped and 6×9.
+        assert_eq!(typed(5, 9, 54), Some(Mistake::Knowledge));
+        // Nothing like the answer.
+        assert_eq!(typed(7, 8, 57), Some(Mistake::Knowledge));
+        assert!(!Mistake::Knowledge.is_slip());
+    }
+
+    #[test]
+    fn picked_answers_are_never_slips() {
+        assert_eq!(
+            classify(&Problem::new(7, 8), 65, AnswerMode::MultipleChoice),
+            Some(Mistake::Knowledge)
+        );
+    }
+}
@@ -1,17 +1,78 @@
-use crate::problem::{generate_all_problems, Problem, ProblemStats, TABLE_ORDER};
+use crate::choice::AnswerMode;
+use crate::daily::{
+    day_of, local_utc_offset_mins, DailyGoal, DailyProgress, DailySummary, DaySnapshot,
+};
+use crate::focus::Focus;
+use crate::hint::{self, Hint};
+use crate::journal::{AppliedAnswer, ClientAnswer, Journal, JournalEntry, Tally};
+use crate::migrate::{self, LoadError, CURRENT_VERSION};
+use crate::problem::{generate_all_problems, Problem, ProblemStats, TimeThresholds, TABLE_ORDER};
+use crate::slip::{self, Mistake};
+use crate::sprint::{SprintRecords, SprintResult};
+use crate::stats::{DayStats, Statistics, TableStats};
+use crate::worksheet::{Worksheet, WorksheetOptions};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::collections::HashSet;
 
+/// `UNLOCK_MASKS[n]` has a bit set for each of the first `n` tables in
+/// `TABLE_ORDER`, so unlock checks are a single AND.
+const UNLOCK_MASKS: [u16; TABLE_ORDER.len() + 1] = {
+    let mut masks = [0u16; TABLE_ORDER.len() + 1];
+    let mut i = 0;
+    while i < TABLE_ORDER.len() {
+        masks[i + 1] = masks[i] | 1 << TABLE_ORDER[i];
+        i += 1;
+    }
+    masks
+};
+
+/// How long after an answer it can still be taken back as a typo.
+pub const UNDO_WINDOW_SECS: i64 = 30;
+
+/// What the last answer changed, kept so it can be undone.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SpacedRepetition {
-    stats: HashMap<String, ProblemStats>,
-    #[serde(default = "default_unlocked")]
+struct Undo {
+    /// The answer's journal entry, removed along with it.
+    id: String,
+    /// The fact as it was asked.
+    problem: Problem,
+    /// When the answer was recorded, which for a replayed answer is later
+    /// than when it was given.
+    answered_at: DateTime<Utc>,
+    /// The stats entry the answer was recorded in, as it was before.
+    stats: ProblemStats,
     unlocked_tables: usize,
+    day: DaySnapshot,
 }
 
-fn default_unlocked() -> usize {
-    1
+/// Saved progress. Load saved documents with `from_json`, which upgrades
+/// older formats; see `migrate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpacedRepetition {
+    version: u32,
+    /// One entry per fact, indexed by `Problem::index`.
+    #[serde(with = "stats_by_key")]
+    stats: Vec<ProblemStats>,
+    unlocked_tables: usize,
+    /// Link each fact with its commuted twin (3×7 and 7×3). The pair shares
+    /// one memory state, held by the entry with the smaller first factor, and
+    /// counts once toward mastery.
+    commutative: bool,
+    /// What counts as a fast or slow answer when scheduling.
+    time_thresholds: TimeThresholds,
+    sprints: SprintRecords,
+    daily: DailyProgress,
+    journal: Journal,
+    /// When the learner last started over, if ever. Merging never brings back
+    /// progress from before the newer of two resets.
+    reset_at: Option<DateTime<Utc>>,
+    /// When a setting that travels with the progress was last changed, if
+    /// ever. Merging keeps the settings changed last.
+    settings_changed_at: Option<DateTime<Utc>>,
+    /// Saved so an undo survives a reload between requests. It is left out of
+    /// the versioned format: documents without it just have nothing to undo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    undo: Option<Undo>,
 }
 
 impl Default for SpacedRepetition {
@@ -22,28 +83,87 @@ impl Default for SpacedRepetition {
 
 impl SpacedRepetition {
     pub fn new() -> Self {
-        let mut stats = HashMap::new();
-        for problem in generate_all_problems() {
-            stats.insert(problem.key(), ProblemStats::new(problem));
-        }
+        let stats = generate_all_problems()
+            .into_iter()
+            .map(ProblemStats::new)
+            .collect();
         Self {
+            version: CURRENT_VERSION,
             stats,
             unlocked_tables: 1,
+            commutative: false,
+            time_thresholds: TimeThresholds::default(),
+            sprints: SprintRecords::default(),
+            daily: DailyProgress::default(),
+            journal: Journal::default(),
+            reset_at: None,
+            settings_changed_at: None,
+            undo: None,
         }
     }
 
-    fn unlocked_table_set(&self) -> HashSet<u8> {
-        TABLE_ORDER
-            .iter()
-            .take(self.unlocked_tables)
-            .copied()
-            .collect()
+    /// Starts over from nothing, settings included. The reset is kept, so a
+    /// device that still has the old progress can't merge it back in.
+    pub fn reset(&mut self) {
+        *self = Self {
+            reset_at: Some(Utc::now()),
+            ..Self::new()
+        };
     }
 
-    fn is_problem_unlocked(&self, problem: &Problem) -> bool {
-        let unlocked = self.unlocked_table_set();
+    /// Loads a saved document, upgrading it from older format versions.
+    pub fn from_json(json: &str) -> Result<Self, LoadError> {
+        let value = migrate::upgrade(serde_json::from_str(json)?)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Bitmask of unlocked tables: bit `n` is set if the n× table is unlocked.
+    fn unlocked_mask(&self) -> u16 {
+        UNLOCK_MASKS[self.unlocked_tables.min(TABLE_ORDER.len())]
+    }
+
+    /// Facts outside the tables have no bit in the mask, and are never
+    /// unlocked.
+    fn is_problem_unlocked(mask: u16, problem: &Problem) -> bool {
+        if problem.index().is_none() {
+            return false;
+        }
         let (a, b) = problem.tables_required();
-        unlocked.contains(&a) || unlocked.contains(&b)
+        mask & (1 << a | 1 << b) != 0
+    }
+
+    /// A problem can be drawn if it is in the focus set, or, without a focus,
+    /// if it belongs to an unlocked table.
+    fn is_selectable(mask: u16, problem: &Problem, focus: Option<&Focus>) -> bool {
+        match focus {
+            Some(focus) => focus.contains(problem),
+            None => Self::is_problem_unlocked(mask, problem),
+        }
+    }
+
+    /// The entry that holds a fact's memory state: the fact itself, or with
+    /// commutative pairs, whichever of the pair has the smaller first factor.
+    fn canonical(&self, problem: &Problem) -> Problem {
+        if self.commutative && problem.a > problem.b {
+            problem.commuted()
+        } else {
+            *problem
+        }
+    }
+
+    /// Stats that are scheduled and counted: all of them, or one per pair.
+    fn tracked_stats(&self) -> impl Iterator<Item = &ProblemStats> {
+        let commutative = self.commutative;
+        self.stats
+            .iter()
+            .filter(move |s| !commutative || s.problem.a <= s.problem.b)
+    }
+
+    /// Tracked stats for the problems in unlocked tables.
+    fn unlocked_stats(&self) -> impl Iterator<Item = &ProblemStats> {
+        let mask = self.unlocked_mask();
+        self.tracked_stats()
+            .filter(move |s| Self::is_problem_unlocked(mask, &s.problem))
     }
 
     fn check_unlock_next_table(&mut self) {
@@ -51,105 +171,525 @@ impl SpacedRepetition {
             return;
         }
 
-        let unlocked_problems: Vec<_> = self
-            .stats
-            .values()
-            .filter(|s| self.is_problem_unlocked(&s.problem))
-            .collect();
+        let (total, mastered) = self
+            .unlocked_stats()
+            .fold((0, 0), |(total, mastered), s| {
+                (total + 1, mastered + s.is_mastered() as usize)
+            });
 
-        if unlocked_problems.is_empty() {
+        if total == 0 {
             return;
         }
 
-        let mastered = unlocked_problems.iter().filter(|s| s.is_mastered()).count();
-        let total = unlocked_problems.len();
-
         if mastered >= total * 3 / 4 {
             self.unlocked_tables += 1;
         }
     }
 
-    pub fn get_next_problem(&self, last: Option<&Problem>) -> Option<Problem> {
-        let mut due_problems: Vec<_> = self
-            .stats
-            .values()
+    /// The selectable problem with the lowest ease factor. Ties go to the
+    /// first in grid order.
+    fn hardest_selectable(
+        &self,
+        last: Option<&Problem>,
+        focus: Option<&Focus>,
+        due_only: bool,
+    ) -> Option<Problem> {
+        let mask = self.unlocked_mask();
+        let now = Utc::now();
+        let last = last.map(|l| self.canonical(l));
+        let in_focus = |p: &Problem| {
+            Self::is_selectable(mask, p, focus)
+                || (self.commutative && Self::is_selectable(mask, &p.commuted(), focus))
+        };
+        let stats = self
+            .tracked_stats()
             .filter(|s| {
-                s.is_due()
-                    && self.is_problem_unlocked(&s.problem)
-                    && last.map_or(true, |l| s.problem != *l)
+                (!due_only || s.is_due_at(now))
+                    && in_focus(&s.problem)
+                    && last.is_none_or(|l| s.problem != l)
             })
-            .collect();
+            .min_by(|a, b| a.ease_factor.total_cmp(&b.ease_factor))?;
 
-        if due_problems.is_empty() {
-            return None;
+        // Alternate the way round a linked pair is shown, so both get seen.
+        let answers = stats.times_correct + stats.times_wrong;
+        if self.commutative && answers % 2 == 1 {
+            Some(stats.problem.commuted())
+        } else {
+            Some(stats.problem)
         }
+    }
+
+    pub fn get_next_problem(
+        &self,
+        last: Option<&Problem>,
+        focus: Option<&Focus>,
+    ) -> Option<Problem> {
+        self.hardest_selectable(last, focus, true)
+    }
 
-        due_problems.sort_by(|a, b| {
-            a.ease_factor
-                .partial_cmp(&b.ease_factor)
-                .unwrap_or(std::cmp::Ordering::Equal)
+    pub fn get_extra_practice_problem(
+        &self,
+        last: Option<&Problem>,
+        focus: Option<&Focus>,
+    ) -> Option<Problem> {
+        self.hardest_selectable(last, focus, false)
+    }
+
+    pub fn record_answer(&mut self, problem: &Problem, correct: bool, response_secs: f64) {
+        self.record_answer_with_mode(problem, correct, response_secs, AnswerMode::Typed);
+    }
+
+    /// Records an answer given in `mode`; see `ProblemStats::record_answer_with_mode`.
+    pub fn record_answer_with_mode(
+        &mut self,
+        problem: &Problem,
+        correct: bool,
+        response_secs: f64,
+        mode: AnswerMode,
+    ) {
+        let now = Utc::now();
+        self.record(JournalEntry {
+            id: JournalEntry::local_id(problem, now),
+            problem: *problem,
+            correct,
+            mistake: None,
+            response_secs,
+            mode,
+            answered_at: now,
+            utc_offset_mins: Some(local_utc_offset_mins()),
         });
+    }
 
-        due_problems.first().map(|s| s.problem)
+    /// Records the answer the learner gave and returns why it was wrong, if it
+    /// was. Likely keyboard slips cost much less than knowledge errors; see
+    /// `slip`.
+    pub fn record_given_answer(
+        &mut self,
+        problem: &Problem,
+        answer: u32,
+        response_secs: f64,
+        mode: AnswerMode,
+    ) -> Option<Mistake> {
+        let utc_offset_mins = local_utc_offset_mins();
+        self.record_given_answer_in(problem, answer, response_secs, mode, utc_offset_mins)
     }
 
-    pub fn get_extra_practice_problem(&self, last: Option<&Problem>) -> Option<Problem> {
-        let mut unlocked: Vec<_> = self
-            .stats
-            .values()
-            .filter(|s| {
-                self.is_problem_unlocked(&s.problem)
-                    && last.map_or(true, |l| s.problem != *l)
+    /// Like `record_given_answer`, for a learner whose clock is
+    /// `utc_offset_mins` minutes east of UTC: the answer counts toward their
+    /// day rather than this machine's.
+    pub fn record_given_answer_in(
+        &mut self,
+        problem: &Problem,
+        answer: u32,
+        response_secs: f64,
+        mode: AnswerMode,
+        utc_offset_mins: i32,
+    ) -> Option<Mistake> {
+        let now = Utc::now();
+        self.record_client_answer(&ClientAnswer {
+            id: JournalEntry::local_id(problem, now),
+            problem: *problem,
+            answer,
+            response_secs,
+            mode,
+            answered_at: now,
+            utc_offset_mins: Some(utc_offset_mins),
+        })
+    }
+
+    /// Like `record_given_answer_in`, for an answer in a sprint. The sprint
+    /// scores it straight away, so it can't be undone.
+    pub fn record_sprint_answer_in(
+        &mut self,
+        problem: &Problem,
+        answer: u32,
+        response_secs: f64,
+        mode: AnswerMode,
+        utc_offset_mins: i32,
+    ) -> Option<Mistake> {
+        let mistake =
+            self.record_given_answer_in(problem, answer, response_secs, mode, utc_offset_mins);
+        self.undo = None;
+        mistake
+    }
+
+    fn record_client_answer(&mut self, answer: &ClientAnswer) -> Option<Mistake> {
+        let mistake = slip::classify(&answer.problem, answer.answer, answer.mode);
+        self.record(answer.to_entry(mistake));
+        mistake
+    }
+
+    /// Applies answers given elsewhere, e.g. while offline, in the order
+    /// given and as of the time each was given. Answers whose id has been
+    /// applied before are skipped, so a client can safely send a batch again,
+    /// and answers to facts outside the tables are dropped; neither is
+    /// reported as applied.
+    /// Times in the future are taken as now. Answers that don't say how far
+    /// the client's clock is from UTC are taken as `utc_offset_mins`.
+    pub fn apply_answers(
+        &mut self,
+        answers: &[ClientAnswer],
+        utc_offset_mins: i32,
+    ) -> Vec<AppliedAnswer> {
+        let now = Utc::now();
+        let mut seen = self.journal.ids();
+        answers
+            .iter()
+            .map(|answer| {
+                let mistake = slip::classify(&answer.problem, answer.answer, answer.mode);
+                let applied = seen.insert(answer.id.clone())
+                    && self.record(
+                        ClientAnswer {
+                            answered_at: answer.answered_at.min(now),
+                            utc_offset_mins: answer.utc_offset_mins.or(Some(utc_offset_mins)),
+                            ..answer.clone()
+                        }
+                        .to_entry(mistake),
+                    );
+                AppliedAnswer {
+                    id: answer.id.clone(),
+                    applied,
+                    correct: mistake.is_none(),
+                    mistake,
+                }
             })
+            .collect()
+    }
+
+    /// Combines progress from another device into this one, so a learner who
+    /// practised in two places keeps everything from both. The result only
+    /// depends on the two states, and the rules are:
+    ///
+    /// - Answers in both journals are counted once. Correct, wrong and
+    ///   recognised counts add the answers only one side has, and never end up
+    ///   below either side's own count, even where the journals don't reach
+    ///   back far enough to tell.
+    /// - Each fact keeps the higher `best_tier`, and takes its schedule from
+    ///   the side that answered it last. On a tie, the side with more answers
+    ///   wins, then the later review, then this side.
+    /// - Daily tallies combine the same way per day; a day met or frozen on
+    ///   either side stays so. Sprint history is combined and the higher best
+    ///   of each kind kept. The more tables unlocked, the better.
+    /// - Settings (commutative pairs, time thresholds, the daily goal and
+    ///   streak freezes) are the ones changed last, so both sides end up with
+    ///   the same. The side whose settings lose is switched before combining.
+    /// - An answer undone on either side is taken out of both.
+    /// - If one side was reset more recently, it wins outright: the other
+    ///   side only adds the answers it was given after that reset.
+    pub fn merge(&mut self, other: &SpacedRepetition) {
+        if other.reset_at > self.reset_at {
+            let mine = std::mem::replace(self, other.clone());
+            self.replay_since_reset(&mine);
+            return;
+        }
+        if other.reset_at < self.reset_at {
+            self.replay_since_reset(other);
+            return;
+        }
+
+        let mut other = other.clone();
+        if other.settings_key() > self.settings_key() {
+            self.adopt_settings(&other);
+        } else {
+            other.adopt_settings(self);
+        }
+        other.forget(self.journal.undone());
+        self.forget(other.journal.undone());
+
+        let only_mine = self.journal.missing_from(&other.journal);
+        let only_theirs = other.journal.missing_from(&self.journal);
+        let mine_by_fact = self.tally_by_fact(only_mine.iter().copied());
+        let theirs_by_fact = self.tally_by_fact(only_theirs.iter().copied());
+        let mine_by_day = Tally::by_day(&only_mine);
+        let theirs_by_day = Tally::by_day(&only_theirs);
+        let last_mine = self.tally_by_fact(self.journal.entries().iter());
+        let last_theirs = self.tally_by_fact(other.journal.entries().iter());
+
+        for (i, (mine, theirs)) in self.stats.iter_mut().zip(&other.stats).enumerate() {
+            let theirs_is_newer = last_theirs[i]
+                .last
+                .cmp(&last_mine[i].last)
+                .then_with(|| {
+                    (theirs.times_correct + theirs.times_wrong)
+                        .cmp(&(mine.times_correct + mine.times_wrong))
+                })
+                .then_with(|| theirs.next_review.cmp(&mine.next_review))
+                .is_gt();
+            mine.merge(
+                theirs,
+                &mine_by_fact[i],
+                &theirs_by_fact[i],
+                theirs_is_newer,
+            );
+        }
+
+        self.daily.merge(&other.daily, &mine_by_day, &theirs_by_day);
+        self.sprints.merge(&other.sprints);
+        self.journal.merge(&other.journal);
+        self.unlocked_tables = self.unlocked_tables.max(other.unlocked_tables);
+        self.undo = None;
+        self.check_unlock_next_table();
+    }
+
+    /// Orders two sides' settings by when they were changed. Settings changed
+    /// at the same moment, or never, are ordered by their values, so either
+    /// side picks the same.
+    fn settings_key(&self) -> (Option<DateTime<Utc>>, bool, f64, f64, DailyGoal, bool) {
+        (
+            self.settings_changed_at,
+            self.commutative,
+            self.time_thresholds.fast_secs,
+            self.time_thresholds.slow_secs,
+            self.daily.goal,
+            self.daily.freeze_enabled,
+        )
+    }
+
+    fn adopt_settings(&mut self, other: &SpacedRepetition) {
+        self.switch_commutative(other.commutative);
+        self.time_thresholds = other.time_thresholds;
+        self.daily.goal = other.daily.goal;
+        self.daily.freeze_enabled = other.daily.freeze_enabled;
+        self.settings_changed_at = other.settings_changed_at;
+    }
+
+    /// Records the answers `other` was given after this side's reset that
+    /// aren't here yet, in the order they were given.
+    fn replay_since_reset(&mut self, other: &SpacedRepetition) {
+        let since: Vec<JournalEntry> = other
+            .journal
+            .entries()
+            .iter()
+            .filter(|e| Some(e.answered_at) > self.reset_at && !self.journal.contains(&e.id))
+            .cloned()
             .collect();
+        for entry in since {
+            self.record(entry);
+        }
+        self.undo = None;
+    }
 
-        if unlocked.is_empty() {
-            return None;
+    /// Takes answers undone elsewhere out of this side's journal and counts.
+    fn forget(&mut self, ids: &[String]) {
+        let removed = self.journal.undo(ids);
+        let by_fact = self.tally_by_fact(removed.iter());
+        for (stats, tally) in self.stats.iter_mut().zip(&by_fact) {
+            stats.forget(tally);
+        }
+        self.daily
+            .forget(&Tally::by_day(&removed.iter().collect::<Vec<_>>()));
+    }
+
+    /// Tallies answers by the stats entry that schedules their fact.
+    fn tally_by_fact<'a>(&self, entries: impl Iterator<Item = &'a JournalEntry>) -> Vec<Tally> {
+        let mut tallies = vec![Tally::default(); self.stats.len()];
+        for entry in entries {
+            let index = self.canonical(&entry.problem).index();
+            if let Some(tally) = index.and_then(|i| tallies.get_mut(i)) {
+                tally.add(entry);
+            }
         }
+        tallies
+    }
+
+    /// The most recent answers, oldest first; see `journal`.
+    pub fn journal(&self) -> &[JournalEntry] {
+        self.journal.entries()
+    }
+
+    /// The journal's answers to `problem`, oldest first. With commutative
+    /// pairs this includes answers to the pair's other way round.
+    pub fn history_for(&self, problem: &Problem) -> impl Iterator<Item = &JournalEntry> {
+        let fact = self.canonical(problem);
+        self.journal
+            .entries()
+            .iter()
+            .filter(move |e| self.canonical(&e.problem) == fact)
+    }
 
-        unlocked.sort_by(|a, b| {
-            a.ease_factor
-                .partial_cmp(&b.ease_factor)
-                .unwrap_or(std::cmp::Ordering::Equal)
+    /// Applies an answer to the stats that schedule its fact, then counts it
+    /// toward the learner's day and unlocking, keeping what it changed for
+    /// undo. Returns false for facts outside the tables, which count for
+    /// nothing.
+    fn record(&mut self, entry: JournalEntry) -> bool {
+        let problem = self.canonical(&entry.problem);
+        let unlocked_tables = self.unlocked_tables;
+        // Facts outside the tables count for nothing: not the goal, the
+        // streak or the journal.
+        let Some(stats) = problem.index().and_then(|i| self.stats.get_mut(i)) else {
+            return false;
+        };
+        let before = stats.clone();
+        if entry.is_slip() {
+            stats.record_slip_at(entry.answered_at);
+        } else {
+            stats.record_answer_at(
+                entry.correct,
+                entry.response_secs,
+                entry.mode,
+                entry.answered_at,
+                self.time_thresholds,
+            );
+        }
+        let date = entry.date();
+        let day = self
+            .daily
+            .record_with_snapshot(date, entry.correct, entry.response_secs);
+        self.check_unlock_next_table();
+        self.daily.set_mastered(date, self.mastered_count() as u32);
+        self.undo = Some(Undo {
+            id: entry.id.clone(),
+            problem: entry.problem,
+            answered_at: Utc::now(),
+            stats: before,
+            unlocked_tables,
+            day,
         });
+        self.journal.push(entry);
+        true
+    }
 
-        unlocked.first().map(|s| s.problem)
+    /// True if the last answer was given within `UNDO_WINDOW_SECS` and hasn't
+    /// been undone yet.
+    pub fn can_undo_at(&self, now: DateTime<Utc>) -> bool {
+        self.undo
+            .as_ref()
+            .is_some_and(|u| now - u.answered_at <= Duration::seconds(UNDO_WINDOW_SECS))
     }
 
-    pub fn record_answer(&mut self, problem: &Problem, correct: bool, response_secs: f64) {
-        if let Some(stats) = self.stats.get_mut(&problem.key()) {
-            stats.record_answer(correct, response_secs);
+    pub fn can_undo(&self) -> bool {
+        self.can_undo_at(Utc::now())
+    }
+
+    /// Takes back the last answer, e.g. 65 typed for 56: the fact's schedule,
+    /// the unlocked tables and today's tally go back to how they were. Only
+    /// the last answer can be undone, once, within `UNDO_WINDOW_SECS`.
+    /// Returns the fact as it was asked.
+    pub fn undo_last_answer(&mut self) -> Option<Problem> {
+        if !self.can_undo() {
+            return None;
         }
+        let undo = self.undo.take()?;
+        let index = undo.stats.problem.index()?;
+        self.stats[index] = undo.stats;
+        self.unlocked_tables = undo.unlocked_tables;
+        self.daily.restore(undo.day);
+        self.journal.undo(&[undo.id]);
+        Some(undo.problem)
+    }
+
+    pub fn is_commutative(&self) -> bool {
+        self.commutative
+    }
+
+    /// Turns commutative pairs on or off. Either way, both facts of each pair
+    /// take on the stronger of their two schedules, so switching never makes a
+    /// learner redo facts they already know.
+    pub fn set_commutative(&mut self, enabled: bool) {
+        if enabled != self.commutative {
+            self.settings_changed_at = Some(Utc::now());
+            self.switch_commutative(enabled);
+        }
+    }
+
+    fn switch_commutative(&mut self, enabled: bool) {
+        if enabled == self.commutative {
+            return;
+        }
+        for problem in generate_all_problems().iter().filter(|p| p.a < p.b) {
+            let (Some(i), Some(j)) = (problem.index(), problem.commuted().index()) else {
+                continue;
+            };
+            let stronger = if self.stats[j].interval_days > self.stats[i].interval_days {
+                self.stats[j].clone()
+            } else {
+                self.stats[i].clone()
+            };
+            self.stats[i].adopt_schedule(&stronger);
+            self.stats[j].adopt_schedule(&stronger);
+        }
+        self.commutative = enabled;
         self.check_unlock_next_table();
+        // The saved stats entry no longer matches how facts are shared.
+        self.undo = None;
+    }
+
+    pub fn time_thresholds(&self) -> TimeThresholds {
+        self.time_thresholds
+    }
+
+    /// Changes what counts as a fast or slow answer from the next answer on.
+    pub fn set_time_thresholds(&mut self, thresholds: TimeThresholds) {
+        let thresholds = thresholds.clamped();
+        if thresholds != self.time_thresholds {
+            self.settings_changed_at = Some(Utc::now());
+            self.time_thresholds = thresholds;
+        }
+    }
+
+    pub fn daily(&self) -> &DailyProgress {
+        &self.daily
+    }
+
+    /// Today's progress toward the daily goal, using the local calendar day.
+    pub fn daily_summary(&self) -> DailySummary {
+        self.daily_summary_in(local_utc_offset_mins())
+    }
+
+    /// Today's progress for a learner whose clock is `utc_offset_mins`
+    /// minutes east of UTC.
+    pub fn daily_summary_in(&self, utc_offset_mins: i32) -> DailySummary {
+        self.daily.summary(day_of(Utc::now(), Some(utc_offset_mins)))
+    }
+
+    pub fn set_daily_goal(&mut self, goal: DailyGoal) {
+        self.set_daily_goal_in(goal, local_utc_offset_mins());
+    }
+
+    /// Changes the goal, re-checking today by a clock `utc_offset_mins`
+    /// minutes east of UTC.
+    pub fn set_daily_goal_in(&mut self, goal: DailyGoal, utc_offset_mins: i32) {
+        if goal != self.daily.goal {
+            self.settings_changed_at = Some(Utc::now());
+        }
+        self.daily.set_goal(goal, day_of(Utc::now(), Some(utc_offset_mins)));
+    }
+
+    pub fn set_streak_freeze(&mut self, enabled: bool) {
+        if enabled != self.daily.freeze_enabled {
+            self.settings_changed_at = Some(Utc::now());
+            self.daily.freeze_enabled = enabled;
+        }
+    }
+
+    /// Saves a finished sprint. Returns true if it is a new personal best.
+    pub fn record_sprint(&mut self, result: SprintResult) -> bool {
+        self.sprints.record(result)
+    }
+
+    pub fn sprint_records(&self) -> &SprintRecords {
+        &self.sprints
     }
 
     pub fn unlocked_problems(&self) -> usize {
-        self.stats
-            .values()
-            .filter(|s| self.is_problem_unlocked(&s.problem))
-            .count()
+        self.unlocked_stats().count()
     }
 
     pub fn mastered_count(&self) -> usize {
-        self.stats
-            .values()
-            .filter(|s| self.is_problem_unlocked(&s.problem) && s.is_mastered())
-            .count()
+        self.unlocked_stats().filter(|s| s.is_mastered()).count()
     }
 
     pub fn due_count(&self) -> usize {
-        self.stats
-            .values()
-            .filter(|s| self.is_problem_unlocked(&s.problem) && s.is_due())
-            .count()
+        let now = Utc::now();
+        self.unlocked_stats().filter(|s| s.is_due_at(now)).count()
     }
 
     pub fn total_correct(&self) -> u32 {
-        self.stats.values().map(|s| s.times_correct).sum()
+        self.stats.iter().map(|s| s.times_correct).sum()
     }
 
     pub fn total_wrong(&self) -> u32 {
-        self.stats.values().map(|s| s.times_wrong).sum()
+        self.stats.iter().map(|s| s.times_wrong).sum()
     }
 
     pub fn unlocked_tables_display(&self) -> String {
@@ -165,21 +705,411 @@ impl SpacedRepetition {
         TABLE_ORDER.get(self.unlocked_tables).copied()
     }
 
+    /// Whether `problem` belongs to an unlocked table.
+    pub fn is_unlocked(&self, problem: &Problem) -> bool {
+        Self::is_problem_unlocked(self.unlocked_mask(), problem)
+    }
+
+    /// The stats that schedule `problem`. With commutative pairs this is the
+    /// shared entry, so 7×3 reports 3×7's state.
+    pub fn stats_for(&self, problem: &Problem) -> Option<&ProblemStats> {
+        self.canonical(problem).index().map(|i| &self.stats[i])
+    }
+
+    /// A strategy for working out `problem`, built on the facts this learner
+    /// knows best.
+    pub fn hint_for(&self, problem: &Problem) -> Hint {
+        hint::hint_for(problem, |p| self.stats_for(p))
+    }
+
+    /// Day-by-day practice and how each table is going, for a statistics
+    /// view.
+    pub fn statistics(&self) -> Statistics {
+        let days = self
+            .daily
+            .calendar()
+            .map(|(date, day)| DayStats::new(date, day))
+            .collect();
+        let tables = (1..=12u8)
+            .map(|table| {
+                let in_table = |p: &Problem| p.a == table || p.b == table;
+                TableStats::new(
+                    table,
+                    self.is_unlocked(&Problem::new(table, table)),
+                    self.tracked_stats().filter(|s| in_table(&s.problem)),
+                    self.journal.entries().iter().filter(|e| in_table(&e.problem)),
+                )
+            })
+            .collect();
+        Statistics { days, tables }
+    }
+
+    /// A paper worksheet of the due and weakest facts in the unlocked tables.
+    pub fn worksheet(&self, options: WorksheetOptions) -> Worksheet {
+        Worksheet::pick(self.unlocked_stats(), options, self.commutative, Utc::now())
+    }
+
     /// Returns a 144-element vec (a=1..12, b=1..12) with the achievement tier of each cell.
+    /// Both cells of a commutative pair show the pair's shared tier.
     pub fn grid_status(&self) -> Vec<&'static str> {
-        (1u8..=12)
-            .flat_map(|a| {
-                (1u8..=12).map(move |b| {
-                    let key = Problem::new(a, b).key();
-                    match self.stats.get(&key).map(|s| s.best_tier) {
-                        Some(4) => "mastered",
-                        Some(3) => "fast",
-                        Some(2) => "solid",
-                        Some(1) => "learning",
-                        _ => "not_started",
-                    }
-                })
+        self.stats
+            .iter()
+            .map(|s| self.stats_for(&s.problem).map_or(0, |s| s.best_tier))
+            .map(|tier| match tier {
+                4 => "mastered",
+                3 => "fast",
+                2 => "solid",
+                1 => "learning",
+                _ => "not_started",
             })
             .collect()
     }
 }
+
+/// Stats are held densely in memory but saved as a map keyed by
+/// `Problem::key`, e.g. `"7x8": { ... }`, which keeps the file readable and
+/// matches what older builds wrote. Facts missing from the map start fresh.
+mod stats_by_key {
+    use crate::problem::{generate_all_problems, Problem, ProblemStats};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(stats: &[ProblemStats], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_map(stats.iter().map(|st| (st.problem.key(), st)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<ProblemStats>, D::Error> {
+        let saved = HashMap::<String, ProblemStats>::deserialize(d)?;
+        let mut stats: Vec<ProblemStats> = generate_all_problems()
+            .into_iter()
+            .map(ProblemStats::new)
+            .collect();
+        for (key, st) in saved {
+            let index = Problem::from_key(&key)
+                .filter(|p| *p == st.problem)
+                .and_then(|p| p.index())
+                .ok_or_else(|| D::Error::custom(format!("unknown problem key {:?}", key)))?;
+            stats[index] = st;
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A learner with a few right, wrong and picked answers behind them.
+    fn practised() -> SpacedRepetition {
+        let mut sr = SpacedRepetition::new();
+        for _ in 0..4 {
+            sr.record_answer(&Problem::new(1, 2), true, 2.0);
+        }
+        sr.record_answer(&Problem::new(1, 5), false, 9.0);
+        sr.record_answer_with_mode(&Problem::new(1, 7), true, 4.0, AnswerMode::MultipleChoice);
+        sr
+    }
+
+    fn json(sr: &SpacedRepetition) -> serde_json::Value {
+        serde_json::to_value(sr).unwrap()
+    }
+
+    #[test]
+    fn commuted_facts_share_one_memory_state() {
+        let mut sr = SpacedRepetition::new();
+        sr.set_commutative(true);
+        sr.record_answer(&Problem::new(7, 3), true, 2.0);
+        sr.record_answer(&Problem::new(3, 7), true, 2.0);
+
+        let shared = sr.stats_for(&Problem::new(3, 7)).unwrap();
+        assert_eq!((shared.times_correct, shared.consecutive_correct), (2, 2));
+        let other_way = sr.stats_for(&Problem::new(7, 3)).unwrap();
+        assert_eq!(other_way.times_correct, 2);
+    }
+
+    #[test]
+    fn a_pair_counts_once_toward_the_unlocked_facts() {
+        let mut sr = SpacedRepetition::new();
+        // 1×1 through 1×12 and 2×1 through 12×1.
+        assert_eq!(sr.unlocked_problems(), 23);
+        sr.set_commutative(true);
+        assert_eq!(sr.unlocked_problems(), 12);
+    }
+
+    #[test]
+    fn switching_pairs_keeps_the_stronger_schedule() {
+        let mut sr = SpacedRepetition::new();
+        for _ in 0..4 {
+            sr.record_answer(&Problem::new(8, 6), true, 2.0);
+        }
+        let interval =
+            |sr: &SpacedRepetition, a, b| sr.stats_for(&Problem::new(a, b)).unwrap().interval_days;
+        let known = interval(&sr, 8, 6);
+        assert!(known > 1.0);
+
+        sr.set_commutative(true);
+        assert_eq!(interval(&sr, 6, 8), known);
+        // Switching back doesn't make either way round start over.
+        sr.set_commutative(false);
+        assert_eq!((interval(&sr, 6, 8), interval(&sr, 8, 6)), (known, known));
+    }
+
+    fn client_answer(
+        id: &str,
+        answered_at: DateTime<Utc>,
+        utc_offset_mins: Option<i32>,
+    ) -> ClientAnswer {
+        ClientAnswer {
+            id: id.to_string(),
+            problem: Problem::new(7, 8),
+            answer: 56,
+            response_secs: 2.0,
+            mode: AnswerMode::Typed,
+            answered_at,
+            utc_offset_mins,
+        }
+    }
+
+    #[test]
+    fn batched_answers_count_toward_the_day_on_the_learners_clock() {
+        let answered_at = Utc::now() - Duration::hours(30);
+        let mut sr = SpacedRepetition::new();
+        // One says where it was given; the other takes the batch's offset.
+        let east = client_answer("east", answered_at, Some(13 * 60));
+        let west = client_answer("west", answered_at, None);
+        sr.apply_answers(&[east, west], -11 * 60);
+
+        let east = day_of(answered_at, Some(13 * 60));
+        let west = day_of(answered_at, Some(-11 * 60));
+        assert_ne!(east, west);
+        assert_eq!(sr.daily().activity(east).correct, 1);
+        assert_eq!(sr.daily().activity(west).correct, 1);
+
+        // The same answers merged in from elsewhere land on the same days.
+        let mut other = SpacedRepetition::new();
+        other.merge(&sr);
+        assert_eq!(other.daily().activity(east).correct, 1);
+        assert_eq!(other.daily().activity(west).correct, 1);
+    }
+
+    #[test]
+    fn sending_the_same_batch_twice_applies_it_once() {
+        let now = Utc::now();
+        let batch = [
+            client_answer("first", now - Duration::minutes(2), Some(0)),
+            client_answer("second", now - Duration::minutes(1), Some(0)),
+            client_answer("first", now, Some(0)),
+        ];
+        let mut sr = SpacedRepetition::new();
+        let results = sr.apply_answers(&batch, 0);
+        let applied: Vec<bool> = results.iter().map(|r| r.applied).collect();
+        assert_eq!(applied, [true, true, false]);
+        let once = json(&sr);
+
+        let results = sr.apply_answers(&batch, 0);
+        assert!(results.iter().all(|r| !r.applied && r.correct));
+        assert_eq!(json(&sr), once);
+        assert_eq!(sr.total_correct(), 2);
+    }
+
+    #[test]
+    fn answers_to_facts_outside_the_tables_are_not_applied() {
+        let mut sr = SpacedRepetition::new();
+        let answer = ClientAnswer {
+            problem: Problem::new(13, 2),
+            answer: 26,
+            ..client_answer("outside", Utc::now(), Some(0))
+        };
+        let results = sr.apply_answers(&[answer], 0);
+        assert!(!results[0].applied);
+        assert!(sr.journal().is_empty());
+    }
+
+    #[test]
+    fn undo_puts_back_the_stats_the_day_and_the_journal() {
+        let problem = Problem::new(7, 8);
+        let mut sr = SpacedRepetition::new();
+        sr.record_answer(&problem, true, 2.0);
+        let stats = serde_json::to_value(sr.stats_for(&problem)).unwrap();
+        let today = serde_json::to_value(sr.daily_summary()).unwrap();
+        let journal = serde_json::to_value(sr.journal()).unwrap();
+
+        sr.record_given_answer(&problem, 65, 2.0, AnswerMode::Typed);
+        assert_eq!(sr.undo_last_answer(), Some(problem));
+        assert_eq!(serde_json::to_value(sr.stats_for(&problem)).unwrap(), stats);
+        assert_eq!(serde_json::to_value(sr.daily_summary()).unwrap(), today);
+        assert_eq!(serde_json::to_value(sr.journal()).unwrap(), journal);
+    }
+
+    #[test]
+    fn an_answer_can_only_be_undone_once() {
+        let mut sr = SpacedRepetition::new();
+        sr.record_answer(&Problem::new(3, 4), true, 2.0);
+        sr.record_answer(&Problem::new(3, 5), false, 2.0);
+        assert!(sr.undo_last_answer().is_some());
+        assert!(!sr.can_undo());
+        assert_eq!(sr.undo_last_answer(), None);
+        assert_eq!(sr.journal().len(), 1);
+        assert_eq!(sr.total_correct(), 1);
+    }
+
+    #[test]
+    fn sprint_answers_cannot_be_undone() {
+        let mut sr = SpacedRepetition::new();
+        sr.record_sprint_answer_in(&Problem::new(3, 4), 13, 2.0, AnswerMode::Typed, 0);
+        assert!(!sr.can_undo());
+        assert_eq!(sr.undo_last_answer(), None);
+        assert_eq!(sr.journal().len(), 1);
+    }
+
+    #[test]
+    fn an_undone_answer_sent_again_stays_undone() {
+        let mut sr = SpacedRepetition::new();
+        let answer = client_answer("typo", Utc::now(), Some(0));
+        sr.apply_answers(std::slice::from_ref(&answer), 0);
+        assert!(sr.undo_last_answer().is_some());
+        assert!(sr.journal().is_empty());
+
+        let results = sr.apply_answers(&[answer], 0);
+        assert!(!results[0].applied);
+        assert_eq!(sr.total_correct(), 0);
+    }
+
+    #[test]
+    fn merging_a_state_with_itself_changes_nothing() {
+        let mut sr = practised();
+        sr.undo = None;
+        let mut merged = sr.clone();
+        merged.merge(&sr);
+        assert_eq!(json(&merged), json(&sr));
+    }
+
+    #[test]
+    fn merging_loses_no_answers_only_one_side_has() {
+        let base = practised();
+        let mut desktop = base.clone();
+        desktop.record_answer(&Problem::new(1, 2), true, 2.0);
+        desktop.record_answer(&Problem::new(1, 3), false, 2.0);
+        let mut web = base.clone();
+        web.record_answer(&Problem::new(1, 2), true, 2.0);
+        web.record_answer_with_mode(&Problem::new(1, 3), true, 2.0, AnswerMode::MultipleChoice);
+
+        let mut one_way = desktop.clone();
+        one_way.merge(&web);
+        let mut other_way = web.clone();
+        other_way.merge(&desktop);
+        for merged in [&one_way, &other_way] {
+            assert_eq!(merged.total_correct(), base.total_correct() + 3);
+            assert_eq!(merged.total_wrong(), base.total_wrong() + 1);
+            assert_eq!(merged.journal().len(), base.journal().len() + 4);
+            let twelve = merged.stats_for(&Problem::new(1, 2)).unwrap();
+            assert_eq!(twelve.times_correct, 6);
+            let three = merged.stats_for(&Problem::new(1, 3)).unwrap();
+            assert_eq!((three.times_correct, three.times_wrong), (1, 1));
+            assert_eq!(three.times_recognised, 1);
+            assert_eq!(merged.daily_summary().done, base.daily_summary().done + 3);
+        }
+        // Both sides tell the same story once they have merged.
+        assert_eq!(json(&one_way)["stats"], json(&other_way)["stats"]);
+    }
+
+    #[test]
+    fn a_reset_is_not_undone_by_merging_older_progress() {
+        let mut old = SpacedRepetition::new();
+        for _ in 0..5 {
+            old.record_answer(&Problem::new(1, 1), true, 2.0);
+        }
+        let mut reset = old.clone();
+        reset.reset();
+        reset.record_answer(&Problem::new(1, 2), true, 2.0);
+
+        let mut merged = reset.clone();
+        merged.merge(&old);
+        assert_eq!(merged.total_correct(), 1);
+
+        // The other way round, the reset wins too, and answers given since
+        // it on the old side are kept.
+        old.record_answer(&Problem::new(1, 3), true, 2.0);
+        old.merge(&reset);
+        assert_eq!(old.total_correct(), 2);
+        assert!(old
+            .journal()
+            .iter()
+            .all(|e| e.problem != Problem::new(1, 1)));
+    }
+
+    #[test]
+    fn an_answer_undone_after_syncing_stays_undone() {
+        let mut server = SpacedRepetition::new();
+        server.record_answer(&Problem::new(1, 4), true, 2.0);
+        let mut desktop = SpacedRepetition::new();
+        desktop.merge(&server);
+        assert_eq!(desktop.total_correct(), 1);
+
+        assert!(server.undo_last_answer().is_some());
+        server.merge(&desktop);
+        assert_eq!(server.total_correct(), 0);
+        assert!(server.journal().is_empty());
+
+        desktop.merge(&server);
+        assert_eq!(desktop.total_correct(), 0);
+        assert!(desktop.journal().is_empty());
+        assert_eq!(desktop.daily_summary().done, 0);
+    }
+
+    #[test]
+    fn merging_picks_the_same_settings_whichever_side_goes_first() {
+        let mut desktop = SpacedRepetition::new();
+        desktop.set_commutative(true);
+        desktop.settings_changed_at = Some(Utc::now() - Duration::hours(1));
+        let mut server = SpacedRepetition::new();
+        server.set_daily_goal(DailyGoal::Minutes(10));
+        server.set_streak_freeze(true);
+        let slower = TimeThresholds {
+            fast_secs: 5.0,
+            slow_secs: 12.0,
+        };
+        server.set_time_thresholds(slower);
+
+        let mut one_way = desktop.clone();
+        one_way.merge(&server);
+        let mut other_way = server.clone();
+        other_way.merge(&desktop);
+        for merged in [&one_way, &other_way] {
+            assert!(!merged.is_commutative());
+            assert_eq!(merged.daily().goal, DailyGoal::Minutes(10));
+            assert!(merged.daily().freeze_enabled);
+            assert_eq!(merged.time_thresholds(), slower);
+        }
+
+        // Settings that were never changed through a setter still agree.
+        desktop.settings_changed_at = None;
+        server.settings_changed_at = None;
+        let mut one_way = desktop.clone();
+        one_way.merge(&server);
+        let mut other_way = server.clone();
+        other_way.merge(&desktop);
+        assert_eq!(one_way.settings_key(), other_way.settings_key());
+    }
+
+    #[test]
+    fn answers_outside_the_tables_count_for_nothing() {
+        let mut sr = SpacedRepetition::new();
+        sr.record_answer(&Problem::new(0, 5), true, 2.0);
+        sr.record_given_answer(&Problem::new(13, 2), 26, 2.0, AnswerMode::Typed);
+        assert!(sr.journal().is_empty());
+        assert_eq!(sr.daily_summary().done, 0);
+        assert!(!sr.can_undo());
+    }
+
+    #[test]
+    fn facts_outside_the_tables_are_never_unlocked() {
+        let mut sr = SpacedRepetition::new();
+        sr.unlocked_tables = TABLE_ORDER.len();
+        assert!(sr.is_unlocked(&Problem::new(12, 12)));
+        for problem in [Problem::new(0, 1), Problem::new(16, 1), Problem::new(1, 200)] {
+            assert!(!sr.is_unlocked(&problem));
+        }
+    }
+}
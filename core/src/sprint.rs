@@ -0,0 +1,367 @@
+use crate::focus::Focus;
+use crate::problem::Problem;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Points for every correct answer, before the speed bonus.
+const BASE_POINTS: u32 = 10;
+/// Largest speed bonus, earned by answering within `BONUS_FULL_SECS`.
+const MAX_SPEED_BONUS: f64 = 10.0;
+const BONUS_FULL_SECS: f64 = 1.0;
+/// No speed bonus at or beyond this response time.
+const BONUS_ZERO_SECS: f64 = 6.0;
+/// Points lost for a wrong answer, so guessing never pays.
+const WRONG_PENALTY: u32 = 5;
+
+/// Finished sprints kept in the history. Older ones are dropped; personal
+/// bests are kept separately and never are.
+pub const SPRINT_HISTORY_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SprintLimit {
+    /// As many problems as possible in this many seconds.
+    Seconds(u32),
+    /// A fixed number of problems, as fast as possible.
+    Problems(u32),
+}
+
+impl SprintLimit {
+    pub fn label(&self) -> String {
+        match self {
+            SprintLimit::Seconds(s) => format!("{} seconds", s),
+            SprintLimit::Problems(n) => format!("{} problems", n),
+        }
+    }
+}
+
+/// Points for a single sprint answer: a base score for being right plus a
+/// bonus that shrinks linearly from 1s to 6s.
+pub fn answer_points(correct: bool, response_secs: f64) -> u32 {
+    if !correct {
+        return 0;
+    }
+    let t =
+        ((response_secs - BONUS_FULL_SECS) / (BONUS_ZERO_SECS - BONUS_FULL_SECS)).clamp(0.0, 1.0);
+    BASE_POINTS + (MAX_SPEED_BONUS * (1.0 - t)).round() as u32
+}
+
+/// A sprint in progress. Answers should also be passed to
+/// `SpacedRepetition::record_answer` so they count toward normal progress.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sprint {
+    pub limit: SprintLimit,
+    pub focus: Option<Focus>,
+    pub started_at: DateTime<Utc>,
+    pub correct: u32,
+    pub wrong: u32,
+    pub score: u32,
+    pub total_response_secs: f64,
+    /// The problem the learner was given last and when, if it hasn't been
+    /// answered yet. Set by frontends that time answers themselves rather
+    /// than trusting the client's time; see `response_secs`.
+    #[serde(default)]
+    pub asked: Option<(Problem, DateTime<Utc>)>,
+}
+
+impl Sprint {
+    pub fn new(limit: SprintLimit, focus: Option<Focus>) -> Self {
+        Self {
+            limit,
+            focus,
+            started_at: Utc::now(),
+            correct: 0,
+            wrong: 0,
+            score: 0,
+            total_response_secs: 0.0,
+            asked: None,
+        }
+    }
+
+    /// Notes that `problem` was given to the learner at `now`.
+    pub fn ask(&mut self, problem: Problem, now: DateTime<Utc>) {
+        self.asked = Some((problem, now));
+    }
+
+    /// Seconds from when `problem` was asked until `now`, or `None` if it
+    /// isn't the problem waiting for an answer.
+    pub fn response_secs(&self, problem: &Problem, now: DateTime<Utc>) -> Option<f64> {
+        let (asked, at) = self.asked.as_ref()?;
+        (asked == problem).then(|| (now - *at).num_milliseconds().max(0) as f64 / 1000.0)
+    }
+
+    pub fn answered(&self) -> u32 {
+        self.correct + self.wrong
+    }
+
+    /// Records an answer and returns the points it earned. The problem that
+    /// was asked counts as answered.
+    pub fn record(&mut self, correct: bool, response_secs: f64) -> u32 {
+        self.asked = None;
+        let points = answer_points(correct, response_secs);
+        if correct {
+            self.correct += 1;
+            self.score += points;
+        } else {
+            self.wrong += 1;
+            self.score = self.score.saturating_sub(WRONG_PENALTY);
+        }
+        self.total_response_secs += response_secs;
+        points
+    }
+
+    /// Seconds left for a timed sprint, `None` for a fixed-count sprint.
+    pub fn remaining_secs(&self, now: DateTime<Utc>) -> Option<f64> {
+        match self.limit {
+            SprintLimit::Seconds(limit) => {
+                let elapsed = (now - self.started_at).num_milliseconds() as f64 / 1000.0;
+                Some((limit as f64 - elapsed).max(0.0))
+            }
+            SprintLimit::Problems(_) => None,
+        }
+    }
+
+    /// Problems left for a fixed-count sprint, `None` for a timed sprint.
+    pub fn remaining_problems(&self) -> Option<u32> {
+        match self.limit {
+            SprintLimit::Problems(n) => Some(n.saturating_sub(self.answered())),
+            SprintLimit::Seconds(_) => None,
+        }
+    }
+
+    pub fn is_over(&self, now: DateTime<Utc>) -> bool {
+        self.remaining_secs(now) == Some(0.0) || self.remaining_problems() == Some(0)
+    }
+
+    pub fn finish(&self, now: DateTime<Utc>) -> SprintResult {
+        let answered = self.answered();
+        SprintResult {
+            limit: self.limit,
+            focus: self.focus.clone(),
+            started_at: self.started_at,
+            finished_at: now,
+            correct: self.correct,
+            wrong: self.wrong,
+            score: self.score,
+            avg_response_secs: if answered > 0 {
+                self.total_response_secs / answered as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SprintResult {
+    pub limit: SprintLimit,
+    pub focus: Option<Focus>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub correct: u32,
+    pub wrong: u32,
+    pub score: u32,
+    pub avg_response_secs: f64,
+}
+
+impl SprintResult {
+    pub fn accuracy(&self) -> f64 {
+        let answered = self.correct + self.wrong;
+        if answered == 0 {
+            0.0
+        } else {
+            self.correct as f64 / answered as f64
+        }
+    }
+}
+
+/// Personal bests are kept per sprint kind: a 60-second sprint on the 7× table
+/// is only compared with other 60-second sprints on the 7× table.
+fn record_key(limit: SprintLimit, focus: Option<&Focus>) -> String {
+    let limit = match limit {
+        SprintLimit::Seconds(s) => format!("{}s", s),
+        SprintLimit::Problems(n) => format!("{}p", n),
+    };
+    match focus {
+        Some(focus) => {
+            let tables: Vec<String> = focus.tables.iter().map(|t| t.to_string()).collect();
+            let facts: Vec<String> = focus.facts.iter().map(|p| p.key()).collect();
+            format!("{}|{}|{}", limit, tables.join(","), facts.join(","))
+        }
+        None => format!("{}|all", limit),
+    }
+}
+
+/// Sprint history and personal bests, saved with the learner's progress.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SprintRecords {
+    history: Vec<SprintResult>,
+    bests: BTreeMap<String, SprintResult>,
+}
+
+impl SprintRecords {
+    /// Adds a finished sprint to the history. Returns true if it is a new
+    /// personal best for its kind.
+    pub fn record(&mut self, result: SprintResult) -> bool {
+        let key = record_key(result.limit, result.focus.as_ref());
+        let is_best = self
+            .bests
+            .get(&key)
+            .is_none_or(|best| result.score > best.score);
+        if is_best {
+            self.bests.insert(key, result.clone());
+        }
+        self.history.push(result);
+        self.trim();
+        is_best
+    }
+
+    pub fn best(&self, limit: SprintLimit, focus: Option<&Focus>) -> Option<&SprintResult> {
+        self.bests.get(&record_key(limit, focus))
+    }
+
+    /// All finished sprints, oldest first.
+    pub fn history(&self) -> &[SprintResult] {
+        &self.history
+    }
+
+    pub fn bests(&self) -> impl Iterator<Item = &SprintResult> {
+        self.bests.values()
+    }
+
+    /// Adds the sprints only `other` has, matched by when they started and
+    /// finished, and keeps the higher best of each kind.
+    pub(crate) fn merge(&mut self, other: &SprintRecords) {
+        for result in &other.history {
+            let known = self
+                .history
+                .iter()
+                .any(|r| r.started_at == result.started_at && r.finished_at == result.finished_at);
+            if !known {
+                self.history.push(result.clone());
+            }
+        }
+        self.history.sort_by_key(|r| r.finished_at);
+        self.trim();
+        for (key, best) in &other.bests {
+            if self
+                .bests
+                .get(key)
+                .is_none_or(|mine| best.score > mine.score)
+            {
+                self.bests.insert(key.clone(), best.clone());
+            }
+        }
+    }
+
+    fn trim(&mut self) {
+        if self.history.len() > SPRINT_HISTORY_LIMIT {
+            let excess = self.history.len() - SPRINT_HISTORY_LIMIT;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn faster_right_answers_score_more() {
+        assert_eq!(answer_points(true, 0.5), 20);
+        assert_eq!(answer_points(true, 1.0), 20);
+        assert_eq!(answer_points(true, 3.5), 15);
+        assert_eq!(answer_points(true, 6.0), 10);
+        assert_eq!(answer_points(true, 30.0), 10);
+        assert_eq!(answer_points(false, 0.5), 0);
+    }
+
+    #[test]
+    fn wrong_answers_cost_points_but_never_go_below_zero() {
+        let mut sprint = Sprint::new(SprintLimit::Problems(10), None);
+        assert_eq!(sprint.record(false, 1.0), 0);
+        assert_eq!(sprint.score, 0);
+        sprint.record(true, 8.0);
+        sprint.record(false, 1.0);
+        assert_eq!(sprint.score, 10 - WRONG_PENALTY);
+        assert_eq!((sprint.correct, sprint.wrong), (1, 2));
+    }
+
+    #[test]
+    fn a_sprint_ends_when_its_time_or_problems_run_out() {
+        let now = Utc::now();
+        let mut timed = Sprint::new(SprintLimit::Seconds(60), None);
+        timed.started_at = now - Duration::seconds(45);
+        assert_eq!(timed.remaining_secs(now), Some(15.0));
+        assert!(!timed.is_over(now));
+        assert!(timed.is_over(now + Duration::seconds(15)));
+        assert_eq!(timed.remaining_secs(now + Duration::seconds(90)), Some(0.0));
+
+        let mut counted = Sprint::new(SprintLimit::Problems(2), None);
+        counted.record(true, 2.0);
+        assert!(!counted.is_over(now));
+        counted.record(false, 2.0);
+        assert!(counted.is_over(now));
+        let result = counted.finish(now);
+        assert_eq!((result.correct, result.wrong), (1, 1));
+        assert_eq!(result.avg_response_secs, 2.0);
+    }
+
+    #[test]
+    fn answers_are_timed_from_when_their_problem_was_asked() {
+        let now = Utc::now();
+        let mut sprint = Sprint::new(SprintLimit::Problems(5), None);
+        sprint.ask(Problem::new(7, 8), now);
+        let later = now + Duration::milliseconds(2500);
+        assert_eq!(sprint.response_secs(&Problem::new(7, 8), later), Some(2.5));
+        assert_eq!(sprint.response_secs(&Problem::new(1, 1), later), None);
+        // Once answered, the same problem can't be answered again.
+        sprint.record(true, 2.5);
+        assert_eq!(sprint.response_secs(&Problem::new(7, 8), later), None);
+    }
+
+    fn result(limit: SprintLimit, score: u32, finished_at: DateTime<Utc>) -> SprintResult {
+        SprintResult {
+            limit,
+            focus: None,
+            started_at: finished_at - Duration::seconds(60),
+            finished_at,
+            correct: score / 10,
+            wrong: 0,
+            score,
+            avg_response_secs: 2.0,
+        }
+    }
+
+    #[test]
+    fn personal_bests_are_kept_per_kind_of_sprint() {
+        let now = Utc::now();
+        let minute = SprintLimit::Seconds(60);
+        let mut records = SprintRecords::default();
+        assert!(records.record(result(minute, 100, now)));
+        assert!(!records.record(result(minute, 80, now)));
+        assert!(!records.record(result(minute, 100, now)));
+        // A different kind of sprint has a best of its own.
+        assert!(records.record(result(SprintLimit::Problems(20), 50, now)));
+        assert!(records.record(result(minute, 120, now)));
+        assert_eq!(records.best(minute, None).unwrap().score, 120);
+        assert_eq!(records.history().len(), 5);
+    }
+
+    #[test]
+    fn the_history_keeps_the_latest_sprints_and_every_best() {
+        let start = Utc::now();
+        let mut records = SprintRecords::default();
+        records.record(result(SprintLimit::Problems(20), 500, start));
+        for i in 0..SPRINT_HISTORY_LIMIT as i64 {
+            let finished_at = start + Duration::minutes(i + 1);
+            records.record(result(SprintLimit::Seconds(60), 10, finished_at));
+        }
+        assert_eq!(records.history().len(), SPRINT_HISTORY_LIMIT);
+        assert!(records.history().iter().all(|r| r.score == 10));
+        let best = records.best(SprintLimit::Problems(20), None).unwrap();
+        assert_eq!(best.score, 500);
+    }
+}
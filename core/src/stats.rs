@@ -0,0 +1,115 @@
+//! Figures for a statistics view: how each day of practice went and how
+//! each table is coming along.
+
+use crate::daily::{DayActivity, MAX_SECS_PER_ANSWER};
+use crate::journal::JournalEntry;
+use crate::problem::ProblemStats;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// One day with practice, from the practice calendar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub correct: u32,
+    pub wrong: u32,
+    /// Capped per answer like the daily goal's minutes.
+    pub practice_secs: f64,
+    /// Facts mastered after the day's last answer, when it was kept.
+    pub mastered: Option<u32>,
+    pub goal_met: bool,
+    /// Covered by a streak freeze rather than practice.
+    pub frozen: bool,
+}
+
+impl DayStats {
+    pub(crate) fn new(date: NaiveDate, day: &DayActivity) -> Self {
+        Self {
+            date,
+            correct: day.correct,
+            wrong: day.wrong,
+            practice_secs: day.practice_secs,
+            mastered: day.mastered,
+            goal_met: day.goal_met,
+            frozen: day.frozen,
+        }
+    }
+
+    pub fn answers(&self) -> u32 {
+        self.correct + self.wrong
+    }
+
+    /// Share of the day's answers that were right; `None` without answers.
+    pub fn accuracy(&self) -> Option<f64> {
+        let answers = self.answers();
+        (answers > 0).then(|| self.correct as f64 / answers as f64)
+    }
+
+    /// Average time per answer, with each answer capped as for practice time.
+    pub fn avg_response_secs(&self) -> Option<f64> {
+        let answers = self.answers();
+        (answers > 0).then(|| self.practice_secs / answers as f64)
+    }
+}
+
+/// How one times table is going: the facts with the table as either factor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableStats {
+    pub table: u8,
+    pub unlocked: bool,
+    /// With commutative pairs each pair counts once.
+    pub facts: u32,
+    pub mastered: u32,
+    pub correct: u32,
+    pub wrong: u32,
+    /// Mean time of the table's answers still in the journal.
+    pub avg_response_secs: Option<f64>,
+}
+
+impl TableStats {
+    pub(crate) fn new<'a>(
+        table: u8,
+        unlocked: bool,
+        facts: impl Iterator<Item = &'a ProblemStats>,
+        answers: impl Iterator<Item = &'a JournalEntry>,
+    ) -> Self {
+        let mut stats = Self {
+            table,
+            unlocked,
+            facts: 0,
+            mastered: 0,
+            correct: 0,
+            wrong: 0,
+            avg_response_secs: None,
+        };
+        for fact in facts {
+            stats.facts += 1;
+            stats.mastered += fact.is_mastered() as u32;
+            stats.correct += fact.times_correct;
+            stats.wrong += fact.times_wrong;
+        }
+
+        let (count, total_secs) = answers.fold((0u32, 0.0), |(count, total), entry| {
+            (
+                count + 1,
+                total + entry.response_secs.clamp(0.0, MAX_SECS_PER_ANSWER),
+            )
+        });
+        stats.avg_response_secs = (count > 0).then(|| total_secs / count as f64);
+        stats
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        let answers = self.correct + self.wrong;
+        (answers > 0).then(|| self.correct as f64 / answers as f64)
+    }
+}
+
+/// Everything a statistics view shows, from `SpacedRepetition::statistics`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    /// Every day with practice or a freeze, oldest first.
+    pub days: Vec<DayStats>,
+    /// Tables 1 to 12 in order.
+    pub tables: Vec<TableStats>,
+}
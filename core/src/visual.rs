@@ -0,0 +1,126 @@
+use crate::problem::Problem;
+use serde::{Deserialize, Serialize};
+
+/// A block of whole rows within an array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Part {
+    pub rows: u8,
+    pub cols: u8,
+}
+
+impl Part {
+    pub fn product(&self) -> u32 {
+        self.rows as u32 * self.cols as u32
+    }
+}
+
+/// An array of `rows` rows of `cols` dots for a fact, read as "a rows of b".
+/// Arrays taller than five rows are split into blocks of fives and tens so
+/// they can be counted in friendly chunks: 6×4 = 5×4 + 1×4.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Visual {
+    pub rows: u8,
+    pub cols: u8,
+    /// The blocks from top to bottom. A single part means no split.
+    pub parts: Vec<Part>,
+    /// What the picture shows, without giving the answer away:
+    /// "6×4 = 5×4 + 1×4" or "3 rows of 7".
+    pub caption: String,
+    /// The caption worked through to the answer, for after answering.
+    pub worked: String,
+}
+
+impl Visual {
+    pub fn new(problem: &Problem) -> Self {
+        let (rows, cols) = (problem.a, problem.b);
+        let first = match rows {
+            r if r > 10 => 10,
+            r if r > 5 && r < 10 => 5,
+            r => r,
+        };
+        let mut parts = vec![Part { rows: first, cols }];
+        if rows > first {
+            parts.push(Part {
+                rows: rows - first,
+                cols,
+            });
+        }
+
+        let (caption, worked) = if parts.len() > 1 {
+            let terms: Vec<String> = parts
+                .iter()
+                .map(|p| format!("{}×{}", p.rows, p.cols))
+                .collect();
+            let products: Vec<String> = parts.iter().map(|p| p.product().to_string()).collect();
+            let caption = format!("{}×{} = {}", rows, cols, terms.join(" + "));
+            let worked = format!(
+                "{} = {} = {}",
+                caption,
+                products.join(" + "),
+                problem.answer()
+            );
+            (caption, worked)
+        } else {
+            let caption = format!("{} rows of {}", rows, cols);
+            let worked = format!("{} = {}", caption, problem.answer());
+            (caption, worked)
+        };
+
+        Self {
+            rows,
+            cols,
+            parts,
+            caption,
+            worked,
+        }
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.parts.len() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::generate_all_problems;
+
+    fn rows_of(visual: &Visual) -> Vec<u8> {
+        visual.parts.iter().map(|p| p.rows).collect()
+    }
+
+    #[test]
+    fn tall_arrays_split_into_fives_and_tens() {
+        let split = |a, b| rows_of(&Visual::new(&Problem::new(a, b)));
+        assert_eq!(split(3, 7), [3]);
+        assert_eq!(split(5, 4), [5]);
+        assert_eq!(split(6, 4), [5, 1]);
+        assert_eq!(split(9, 9), [5, 4]);
+        assert_eq!(split(10, 2), [10]);
+        assert_eq!(split(12, 3), [10, 2]);
+    }
+
+    #[test]
+    fn the_parts_add_up_to_the_whole_array() {
+        for problem in generate_all_problems() {
+            let visual = Visual::new(&problem);
+            assert_eq!(rows_of(&visual).iter().sum::<u8>(), visual.rows);
+            assert!(visual.parts.iter().all(|p| p.cols == problem.b));
+            let total: u32 = visual.parts.iter().map(Part::product).sum();
+            assert_eq!(total, problem.answer());
+        }
+    }
+
+    #[test]
+    fn the_caption_hides_the_answer_until_worked_through() {
+        let split = Visual::new(&Problem::new(6, 4));
+        assert!(split.is_split());
+        assert_eq!(split.caption, "6×4 = 5×4 + 1×4");
+        assert_eq!(split.worked, "6×4 = 5×4 + 1×4 = 20 + 4 = 24");
+
+        let whole = Visual::new(&Problem::new(3, 7));
+        assert!(!whole.is_split());
+        assert_eq!(whole.caption, "3 rows of 7");
+        assert_eq!(whole.worked, "3 rows of 7 = 21");
+    }
+}
@@ -0,0 +1,248 @@
+use crate::problem::{Problem, ProblemStats};
+use crate::rng::SeededRng;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+pub const MAX_PROBLEMS: usize = 200;
+pub const MAX_COLUMNS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorksheetOptions {
+    pub count: usize,
+    pub columns: usize,
+    /// Add the answers on a separate page at the end.
+    pub answer_key: bool,
+    pub seed: u64,
+}
+
+impl Default for WorksheetOptions {
+    fn default() -> Self {
+        Self {
+            count: 30,
+            columns: 3,
+            answer_key: true,
+            seed: 0,
+        }
+    }
+}
+
+/// A sheet of problems for paper practice. The same progress and seed always
+/// give the same sheet, so a teacher can print it again from the seed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Worksheet {
+    pub options: WorksheetOptions,
+    pub problems: Vec<Problem>,
+}
+
+impl Worksheet {
+    /// Picks the due facts first, then the weakest by ease factor. When there
+    /// are fewer facts than places on the sheet they are repeated. With
+    /// `commutative`, either way round of a fact may be printed.
+    pub(crate) fn pick<'a>(
+        stats: impl Iterator<Item = &'a ProblemStats>,
+        options: WorksheetOptions,
+        commutative: bool,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut rng = SeededRng::new(options.seed);
+        let count = options.count.clamp(1, MAX_PROBLEMS);
+
+        let mut candidates: Vec<&ProblemStats> = stats.collect();
+        // Shuffle before the stable sort so the seed decides between equally
+        // weak facts.
+        rng.shuffle(&mut candidates);
+        candidates.sort_by(|a, b| {
+            b.is_due_at(now)
+                .cmp(&a.is_due_at(now))
+                .then(a.ease_factor.total_cmp(&b.ease_factor))
+        });
+
+        let mut problems: Vec<Problem> = candidates
+            .iter()
+            .map(|s| s.problem)
+            .cycle()
+            .take(count)
+            .collect();
+        rng.shuffle(&mut problems);
+        if commutative {
+            for p in &mut problems {
+                if rng.below(2) == 1 {
+                    *p = p.commuted();
+                }
+            }
+        }
+
+        Self {
+            options: WorksheetOptions {
+                count,
+                columns: options.columns.clamp(1, MAX_COLUMNS),
+                ..options
+            },
+            problems,
+        }
+    }
+
+    /// A standalone printable page. Each problem is numbered; the answer key,
+    /// if wanted, starts on a new page.
+    pub fn to_html(&self, title: &str) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\" />\n\
+             <title>{title}</title>\n<style>\n\
+             body {{ font-family: sans-serif; margin: 2rem; }}\n\
+             .details {{ display: flex; gap: 3rem; margin-bottom: 1.5rem; }}\n\
+             .problems {{ display: grid; grid-template-columns: repeat({columns}, 1fr); \
+             gap: 1rem 2rem; font-size: 1.25rem; }}\n\
+             .num {{ color: #666; display: inline-block; width: 2.5rem; }}\n\
+             .key {{ break-before: page; }}\n\
+             .key .problems {{ font-size: 1rem; }}\n\
+             footer {{ margin-top: 2rem; color: #666; font-size: 0.75rem; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n\
+             <div class=\"details\"><span>Name: ____________</span>\
+             <span>Date: ________</span></div>\n",
+            title = escape(title),
+            columns = self.options.columns,
+        );
+
+        html.push_str("<div class=\"problems\">\n");
+        for (i, p) in self.problems.iter().enumerate() {
+            let _ = writeln!(
+                html,
+                "<div><span class=\"num\">{}.</span>{} × {} = ______</div>",
+                i + 1,
+                p.a,
+                p.b
+            );
+        }
+        html.push_str("</div>\n");
+
+        if self.options.answer_key {
+            html.push_str("<section class=\"key\">\n<h2>Answers</h2>\n<div class=\"problems\">\n");
+            for (i, p) in self.problems.iter().enumerate() {
+                let _ = writeln!(
+                    html,
+                    "<div><span class=\"num\">{}.</span>{} × {} = {}</div>",
+                    i + 1,
+                    p.a,
+                    p.b,
+                    p.answer()
+                );
+            }
+            html.push_str("</div>\n</section>\n");
+        }
+
+        let _ = write!(
+            html,
+            "<footer>Sheet {}</footer>\n</body>\n</html>\n",
+            self.options.seed
+        );
+        html
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::generate_all_problems;
+    use chrono::Duration;
+
+    fn stats(now: DateTime<Utc>) -> Vec<ProblemStats> {
+        generate_all_problems()
+            .into_iter()
+            .map(|p| {
+                let mut s = ProblemStats::new(p);
+                s.next_review = now + Duration::days(1);
+                s.ease_factor = 1.3 + (p.a as f64 + p.b as f64) / 10.0;
+                s
+            })
+            .collect()
+    }
+
+    fn sheet(stats: &[ProblemStats], options: WorksheetOptions, now: DateTime<Utc>) -> Worksheet {
+        Worksheet::pick(stats.iter(), options, false, now)
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_sheet() {
+        let now = Utc::now();
+        let stats = stats(now);
+        let options = WorksheetOptions {
+            seed: 7,
+            ..Default::default()
+        };
+        let first = sheet(&stats, options.clone(), now);
+        let again = sheet(&stats, options.clone(), now);
+        assert_eq!(first.problems, again.problems);
+        assert_eq!(first.to_html("Sheet"), again.to_html("Sheet"));
+
+        let other = sheet(&stats, WorksheetOptions { seed: 8, ..options }, now);
+        assert_ne!(first.problems, other.problems);
+    }
+
+    #[test]
+    fn due_facts_come_before_weak_ones() {
+        let now = Utc::now();
+        let mut stats = stats(now);
+        let due = [Problem::new(12, 12), Problem::new(11, 12)];
+        for s in stats.iter_mut().filter(|s| due.contains(&s.problem)) {
+            s.next_review = now;
+        }
+        let options = WorksheetOptions {
+            count: 5,
+            ..Default::default()
+        };
+        let picked = sheet(&stats, options, now).problems;
+        assert!(due.iter().all(|p| picked.contains(p)));
+        // The rest are the weakest facts: the smallest factors here.
+        assert!(picked
+            .iter()
+            .filter(|p| !due.contains(p))
+            .all(|p| p.a + p.b <= 3));
+    }
+
+    #[test]
+    fn few_facts_repeat_and_options_are_clamped() {
+        let now = Utc::now();
+        let stats = vec![ProblemStats::new(Problem::new(3, 4))];
+        let options = WorksheetOptions {
+            count: 1000,
+            columns: 0,
+            ..Default::default()
+        };
+        let sheet = sheet(&stats, options, now);
+        assert_eq!(sheet.problems.len(), MAX_PROBLEMS);
+        assert!(sheet.problems.iter().all(|p| *p == Problem::new(3, 4)));
+        assert_eq!(sheet.options.columns, 1);
+    }
+
+    #[test]
+    fn the_page_escapes_the_title_and_keys_the_answers() {
+        let sheet = Worksheet {
+            options: WorksheetOptions::default(),
+            problems: vec![Problem::new(6, 7)],
+        };
+        let html = sheet.to_html("<Class 3&4>");
+        assert!(html.contains("&lt;Class 3&amp;4&gt;"));
+        assert!(html.contains("6 × 7 = ______"));
+        assert!(html.contains("6 × 7 = 42"));
+
+        let no_key = Worksheet {
+            options: WorksheetOptions {
+                answer_key: false,
+                ..Default::default()
+            },
+            ..sheet
+        };
+        assert!(!no_key.to_html("Sheet").contains("6 × 7 = 42"));
+    }
+}
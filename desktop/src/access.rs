@@ -0,0 +1,28 @@
+//! Screen reader support. Once a screen reader connects, egui describes its
+//! own widgets to it through AccessKit; these fill in what it can't know:
+//! what a drawing shows, and which text should be read out as it changes.
+
+use eframe::egui::{self, accesskit};
+
+/// Gives something drawn by hand, such as a chart, text for screen readers.
+pub fn describe(response: &egui::Response, text: &str) {
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Label, true, text));
+}
+
+/// Reads `text` out in place of the widget's own text, whenever it changes.
+/// `Live::Assertive` interrupts whatever is being read, for news the learner
+/// has to act on such as a wrong answer.
+pub fn announce(response: &egui::Response, text: &str, live: accesskit::Live) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_name(text);
+        node.set_live(live);
+    });
+}
+
+/// Gives a text box the keyboard, unless the learner has moved it to another
+/// widget with Tab.
+pub fn keep_focus(response: &egui::Response) {
+    if response.ctx.memory(|m| m.focused().is_none()) {
+        response.request_focus();
+    }
+}
@@ -1,152 +1,655 @@
+use tt_core::choice::{AnswerMode, MultipleChoice};
+use tt_core::daily::local_utc_offset_mins;
+use tt_core::focus::Focus;
+use tt_core::parse::parse_answer;
 use tt_core::problem::Problem;
+use tt_core::session::Session;
+use tt_core::slip::Mistake;
 use tt_core::spaced_rep::SpacedRepetition;
-use crate::storage;
-use eframe::egui;
-use std::time::Instant;
+use tt_core::sprint::SprintLimit;
+use tt_core::visual::Visual;
+use crate::profile::{self, Profile, ProfileScreen, DEFAULT_NAME};
+use crate::settings::{Settings, SettingsScreen};
+use crate::sync::{SyncScreen, SYNC_INTERVAL};
+use crate::chart::StatsScreen;
+use crate::focus::{FocusAction, FocusPicker};
+use crate::sprint::{self, SprintAction, SprintScreen};
+use crate::worksheet::WorksheetPicker;
+use crate::grid::GridScreen;
+use crate::{access, storage, visual};
+use chrono::Utc;
+use eframe::egui::{self, accesskit::Live};
+use std::time::{Duration, Instant};
+
+/// Options shown in multiple-choice mode, picked with the buttons or keys 1–4.
+const CHOICE_COUNT: usize = 4;
+const CHOICE_KEYS: [egui::Key; CHOICE_COUNT] =
+    [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4];
+
+fn new_session() -> Session {
+    Session::new(Utc::now().format("desktop-%Y%m%dT%H%M%S%.3f").to_string())
+}
 
 #[derive(PartialEq)]
 enum FeedbackState {
     None,
-    Incorrect { correct_answer: u32, user_answer: u32 },
+    Incorrect {
+        correct_answer: u32,
+        user_answer: u32,
+        mistake: Mistake,
+        hint: String,
+    },
 }
 
 pub struct TimesTablesApp {
+    profiles: Vec<Profile>,
+    /// The profile whose progress and settings are loaded.
+    profile_id: String,
+    /// Set at startup until a learner picks their profile, so nobody practises
+    /// as someone else by accident.
+    locked: bool,
+    profile_screen: ProfileScreen,
     spaced_rep: SpacedRepetition,
     current_problem: Option<Problem>,
     last_problem: Option<Problem>,
     problem_start: Instant,
     answer_input: String,
+    /// Why the typed answer couldn't be read. The text is kept to fix.
+    answer_error: Option<String>,
     feedback: FeedbackState,
-    streak: u32,
-    session_correct: u32,
-    session_wrong: u32,
+    /// The fact just answered right, confirmed under the next problem.
+    answered_right: Option<Problem>,
+    /// Options for the current problem in multiple-choice mode.
+    choices: Option<MultipleChoice>,
+    settings: Settings,
+    /// Cleared when the theme or text size may need applying again.
+    settings_applied: bool,
+    settings_screen: SettingsScreen,
+    session: Session,
     confirm_reset: bool,
+    focus: Option<Focus>,
+    focus_picker: FocusPicker,
+    sprint: SprintScreen,
+    grid: GridScreen,
+    stats: StatsScreen,
+    worksheet: WorksheetPicker,
+    sync: SyncScreen,
+    /// Set when saved progress exists but couldn't be loaded. Saving is
+    /// disabled while it is set so the file on disk is left alone.
+    load_error: Option<String>,
+    /// Says that progress was restored from a backup, until dismissed.
+    recovery_notice: Option<String>,
 }
 
-impl Default for TimesTablesApp {
-    fn default() -> Self {
-        let spaced_rep = storage::load_or_new();
-        let mut current_problem = spaced_rep.get_next_problem(None);
+impl TimesTablesApp {
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+        let (profiles, profiles_error) = match storage::load_profiles() {
+            Ok(profiles) => (profiles, None),
+            Err(e) => (vec![Profile::new(profile::next_id(&[]), DEFAULT_NAME)], Some(e)),
+        };
+        let profile_id = storage::load_last_profile()
+            .filter(|id| profiles.iter().any(|p| &p.id == id))
+            .unwrap_or_else(|| profiles[0].id.clone());
+
+        let mut app = Self::open(profiles, profile_id);
+        if profiles_error.is_some() {
+            app.load_error = profiles_error;
+        }
+        // One learner without a PIN goes straight to practice.
+        app.locked = app.profiles.len() > 1 || app.profiles.iter().any(Profile::has_pin);
+        app.profile_screen.open = app.locked;
+        app
+    }
+
+    /// The app as it starts for one profile, with its saved progress, settings
+    /// and account.
+    fn open(profiles: Vec<Profile>, profile_id: String) -> Self {
+        let loaded = storage::load_or_new(&profile_id);
+        let (spaced_rep, recovery_notice, load_error) = match loaded {
+            Ok(loaded) => (loaded.progress, loaded.recovered, None),
+            Err(e) => (SpacedRepetition::new(), None, Some(e)),
+        };
+        let mut current_problem = spaced_rep.get_next_problem(None, None);
         if current_problem.is_none() {
-            current_problem = spaced_rep.get_extra_practice_problem(None);
+            current_problem = spaced_rep.get_extra_practice_problem(None, None);
         }
 
-        Self {
+        let mut app = Self {
+            profiles,
+            locked: false,
+            profile_screen: ProfileScreen::default(),
+            settings: storage::load_settings(&profile_id),
+            settings_applied: false,
+            settings_screen: SettingsScreen::default(),
+            sync: SyncScreen::new(storage::load_account(&profile_id)),
+            profile_id,
             spaced_rep,
             current_problem,
             last_problem: None,
             problem_start: Instant::now(),
             answer_input: String::new(),
+            answer_error: None,
             feedback: FeedbackState::None,
-            streak: 0,
-            session_correct: 0,
-            session_wrong: 0,
+            answered_right: None,
+            choices: None,
+            session: new_session(),
             confirm_reset: false,
+            focus: None,
+            focus_picker: FocusPicker::default(),
+            sprint: SprintScreen::default(),
+            grid: GridScreen::default(),
+            stats: StatsScreen::default(),
+            worksheet: WorksheetPicker::default(),
+            load_error,
+            recovery_notice,
+        };
+        app.deal_choices();
+        app
+    }
+
+    fn save_progress(&self) {
+        if self.load_error.is_none() {
+            let _ = storage::save(&self.profile_id, &self.spaced_rep);
         }
     }
-}
 
-impl TimesTablesApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::default()
+    fn save_settings(&self) {
+        let _ = storage::save_settings(&self.profile_id, &self.settings);
     }
 
     fn submit_answer(&mut self) {
-        let Some(problem) = self.current_problem else {
-            return;
-        };
-
-        let user_answer: u32 = match self.answer_input.trim().parse() {
+        let language = self.settings.locale.language;
+        let user_answer = match parse_answer(&self.answer_input, Some(language)) {
             Ok(n) => n,
-            Err(_) => {
-                self.answer_input.clear();
+            Err(e) => {
+                self.answer_error = Some(e.to_string());
                 return;
             }
         };
+        self.answer_error = None;
+        self.record_answer(user_answer, AnswerMode::Typed);
+    }
+
+    fn pick_choice(&mut self, user_answer: u32) {
+        self.record_answer(user_answer, AnswerMode::MultipleChoice);
+    }
+
+    fn record_answer(&mut self, user_answer: u32, mode: AnswerMode) {
+        let Some(problem) = self.current_problem else {
+            return;
+        };
 
         let response_secs = self.problem_start.elapsed().as_secs_f64();
         let correct_answer = problem.answer();
         let is_correct = user_answer == correct_answer;
 
-        self.spaced_rep.record_answer(&problem, is_correct, response_secs);
+        let mistake = if self.sprint.is_running() {
+            let utc_offset_mins = local_utc_offset_mins();
+            self.spaced_rep.record_sprint_answer_in(
+                &problem,
+                user_answer,
+                response_secs,
+                mode,
+                utc_offset_mins,
+            )
+        } else {
+            self.spaced_rep
+                .record_given_answer(&problem, user_answer, response_secs, mode)
+        };
+        self.session
+            .record_with_mode(problem, user_answer, is_correct, response_secs, mode);
+
+        if self.sprint.is_running() {
+            if self.sprint.record(problem, is_correct, response_secs) {
+                self.finish_sprint();
+            } else {
+                self.save_progress();
+                self.next_problem();
+            }
+            return;
+        }
 
+        self.answered_right = is_correct.then_some(problem);
         if is_correct {
-            self.streak += 1;
-            self.session_correct += 1;
-            let _ = storage::save(&self.spaced_rep);
+            self.save_progress();
             self.next_problem();
         } else {
-            self.feedback = FeedbackState::Incorrect { correct_answer, user_answer };
-            self.streak = 0;
-            self.session_wrong += 1;
+            let hint = self.spaced_rep.hint_for(&problem).text;
+            self.feedback = FeedbackState::Incorrect {
+                correct_answer,
+                user_answer,
+                mistake: mistake.unwrap_or(Mistake::Knowledge),
+                hint,
+            };
             self.answer_input.clear();
-            let _ = storage::save(&self.spaced_rep);
+            self.save_progress();
         }
     }
 
     fn check_correction(&mut self) {
         if let FeedbackState::Incorrect { correct_answer, .. } = self.feedback {
-            if let Ok(typed) = self.answer_input.trim().parse::<u32>() {
-                if typed == correct_answer {
-                    self.next_problem();
-                }
+            match parse_answer(&self.answer_input, Some(self.settings.locale.language)) {
+                Ok(typed) if typed == correct_answer => self.next_problem(),
+                Ok(_) => self.answer_error = None,
+                Err(e) => self.answer_error = Some(e.to_string()),
             }
         }
     }
 
+    /// Takes back a wrong answer that was a typo and asks the same problem
+    /// again.
+    fn undo_answer(&mut self) {
+        if self.spaced_rep.undo_last_answer().is_none() {
+            return;
+        }
+        self.session.undo_last();
+        self.save_progress();
+        self.problem_start = Instant::now();
+        self.answer_input.clear();
+        self.answer_error = None;
+        self.feedback = FeedbackState::None;
+        self.deal_choices();
+    }
+
     fn next_problem(&mut self) {
         self.last_problem = self.current_problem;
-        self.current_problem = self.spaced_rep.get_next_problem(self.last_problem.as_ref());
-        if self.current_problem.is_none() {
-            self.current_problem =
-                self.spaced_rep.get_extra_practice_problem(self.last_problem.as_ref());
+        let last = self.last_problem.as_ref();
+        let focus = self.focus.as_ref();
+        self.current_problem = self
+            .spaced_rep
+            .get_next_problem(last, focus)
+            .or_else(|| self.spaced_rep.get_extra_practice_problem(last, focus))
+            // A one-fact focus has nothing else to offer, so repeat it
+            .or_else(|| self.spaced_rep.get_extra_practice_problem(None, focus));
+        self.problem_start = Instant::now();
+        self.answer_input.clear();
+        self.answer_error = None;
+        self.feedback = FeedbackState::None;
+        self.deal_choices();
+    }
+
+    /// Builds fresh options for the current problem, or clears them when
+    /// answers are typed or multiple choice is switched off.
+    fn deal_choices(&mut self) {
+        self.choices = match (self.settings.answer_mode, self.current_problem) {
+            (AnswerMode::MultipleChoice, Some(problem)) if self.settings.modes.multiple_choice => {
+                let seed = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+                Some(MultipleChoice::new(problem, CHOICE_COUNT, seed))
+            }
+            _ => None,
+        };
+    }
+
+    /// Shows why the last typed answer couldn't be read, if it couldn't, and
+    /// reads it out to screen readers straight away.
+    fn answer_error_label(&self, ui: &mut egui::Ui) {
+        if let Some(error) = &self.answer_error {
+            let label =
+                ui.label(egui::RichText::new(error).color(self.settings.theme.wrong_colour()));
+            access::announce(&label, error, Live::Assertive);
+        }
+    }
+
+    /// Shows the options as a row of buttons and returns the one picked by
+    /// click or number key.
+    fn choice_buttons(&self, ui: &mut egui::Ui, keys_enabled: bool) -> Option<u32> {
+        let choices = self.choices.as_ref()?;
+        let mut picked = None;
+        ui.horizontal(|ui| {
+            // Centre the row; `vertical_centered` doesn't centre a horizontal layout.
+            let width = choices.options.len() as f32 * 88.0;
+            ui.add_space(((ui.available_width() - width) / 2.0).max(0.0));
+            for (i, &option) in choices.options.iter().enumerate() {
+                let button = egui::Button::new(egui::RichText::new(option.to_string()).size(28.0));
+                let key = CHOICE_KEYS.get(i).copied();
+                let pressed =
+                    keys_enabled && key.is_some_and(|k| ui.input(|input| input.key_pressed(k)));
+                if ui.add_sized([80.0, 56.0], button).clicked() || pressed {
+                    picked = Some(option);
+                }
+            }
+        });
+        picked
+    }
+
+    fn start_sprint(&mut self, limit: SprintLimit) {
+        self.sprint.start(limit, self.focus.clone());
+        self.focus_picker.close();
+        self.worksheet.open = false;
+        self.confirm_reset = false;
+        self.next_problem();
+    }
+
+    fn finish_sprint(&mut self) {
+        self.sprint.finish(&mut self.spaced_rep);
+        self.save_progress();
+        self.answer_input.clear();
+        self.answer_error = None;
+    }
+
+    fn sprint_ui(&mut self, ui: &mut egui::Ui) {
+        if self.sprint.is_over() {
+            self.finish_sprint();
+            return;
         }
+
+        ui.vertical_centered(|ui| {
+            self.sprint.status_ui(ui);
+
+            let Some(problem) = self.current_problem else {
+                return;
+            };
+            let locale = self.settings.locale;
+            let question = ui.label(
+                egui::RichText::new(locale.question(&problem))
+                    .size(48.0)
+                    .strong(),
+            );
+            let spoken = self.sprint.spoken_feedback(&locale) + &locale.question_words(&problem);
+            access::announce(&question, &spoken, Live::Polite);
+            ui.add_space(20.0);
+
+            if self.choices.is_some() {
+                if let Some(choice) = self.choice_buttons(ui, true) {
+                    self.pick_choice(choice);
+                }
+            } else {
+                let response = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.answer_input)
+                            .hint_text("Enter answer")
+                            .font(egui::TextStyle::Heading)
+                            .desired_width(150.0)
+                            .horizontal_align(egui::Align::Center),
+                    )
+                    .labelled_by(question.id);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.submit_answer();
+                }
+                access::keep_focus(&response);
+                self.answer_error_label(ui);
+            }
+
+            ui.add_space(10.0);
+            if self.sprint.feedback_ui(ui, &locale, self.settings.theme) {
+                self.finish_sprint();
+            }
+        });
+
+        // Keep the countdown ticking even when nothing is typed.
+        ui.ctx().request_repaint_after(Duration::from_millis(200));
+    }
+
+    fn sprint_result_ui(&mut self, ui: &mut egui::Ui) {
+        let records = self.spaced_rep.sprint_records();
+        match self.sprint.result_ui(ui, records, self.settings.theme) {
+            Some(SprintAction::Again(limit)) => self.start_sprint(limit),
+            Some(SprintAction::Back) => {
+                self.sprint.close();
+                self.next_problem();
+            }
+            None => {}
+        }
+    }
+
+    /// Asks `fact` next, straight from the progress grid.
+    fn practise_fact(&mut self, fact: Problem) {
+        self.grid.open = false;
+        self.answered_right = None;
+        self.last_problem = self.current_problem;
+        self.current_problem = Some(fact);
         self.problem_start = Instant::now();
         self.answer_input.clear();
+        self.answer_error = None;
         self.feedback = FeedbackState::None;
+        self.deal_choices();
+    }
+
+    fn start_focus(&mut self, focus: Focus) {
+        self.focus = Some(focus);
+        self.focus_picker.close();
+        self.current_problem = None;
+        self.next_problem();
+    }
+
+    fn clear_focus(&mut self) {
+        self.focus = None;
+        self.focus_picker.close();
+        self.current_problem = None;
+        self.next_problem();
+    }
+
+    /// A picker with its own inputs, or the reset confirmation, is showing,
+    /// so the answer field shouldn't take the keyboard.
+    fn picker_open(&self) -> bool {
+        self.focus_picker.open || self.worksheet.open || self.sync.open || self.confirm_reset
+    }
+
+    fn current_profile(&self) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == self.profile_id)
+    }
+
+    fn show_profile_screen(&mut self) {
+        self.profile_screen.show();
+        self.confirm_reset = false;
+    }
+
+    /// Loads `id`'s progress and settings in place of the open profile's.
+    /// Picking the open profile keeps its session going.
+    fn switch_profile(&mut self, id: String) {
+        if id != self.profile_id {
+            let profiles = std::mem::take(&mut self.profiles);
+            *self = Self::open(profiles, id);
+        }
+        self.locked = false;
+        self.profile_screen.close();
+        let _ = storage::save_last_profile(&self.profile_id);
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        let settings_before = self.settings.clone();
+        let saves = self
+            .settings_screen
+            .ui(ui, &mut self.settings, &mut self.spaced_rep);
+        if self.settings.modes != settings_before.modes {
+            self.modes_changed();
+        }
+        if self.settings != settings_before {
+            self.settings_applied = false;
+        }
+        if saves.settings {
+            self.save_settings();
+        }
+        if saves.progress {
+            self.save_progress();
+        }
+    }
+
+    /// Puts practice in line with the enabled modes, leaving any that were
+    /// just switched off.
+    fn modes_changed(&mut self) {
+        let modes = self.settings.modes;
+        self.deal_choices();
+        if !modes.focus && self.focus.is_some() {
+            self.clear_focus();
+        }
+        self.focus_picker.open &= modes.focus;
+        self.worksheet.open &= modes.worksheets;
+    }
+
+    /// Closes whatever is showing over practice, for the Escape key.
+    fn go_back(&mut self) {
+        if self.profile_screen.open {
+            self.profile_screen.back(self.locked);
+        } else if self.settings_screen.open {
+            self.settings_screen.open = false;
+        } else if self.stats.open {
+            self.stats.open = false;
+        } else if self.grid.open {
+            self.grid.open = false;
+        } else {
+            self.confirm_reset = false;
+            self.focus_picker.close();
+            self.worksheet.open = false;
+            self.sync.open = false;
+        }
     }
 
     fn reset_progress(&mut self) {
-        self.spaced_rep = SpacedRepetition::new();
-        self.current_problem = self.spaced_rep.get_next_problem(None);
+        self.spaced_rep.reset();
+        self.focus = None;
+        self.current_problem = self.spaced_rep.get_next_problem(None, None);
         self.last_problem = None;
         self.problem_start = Instant::now();
         self.answer_input.clear();
+        self.answer_error = None;
         self.feedback = FeedbackState::None;
-        self.streak = 0;
-        self.session_correct = 0;
-        self.session_wrong = 0;
+        self.answered_right = None;
+        self.deal_choices();
+        self.session = new_session();
         self.confirm_reset = false;
-        let _ = storage::save(&self.spaced_rep);
+        // Starting over is a deliberate choice to replace whatever couldn't be loaded.
+        self.load_error = None;
+        self.save_progress();
     }
 }
 
 impl eframe::App for TimesTablesApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.settings.zoom_with_keyboard(ctx) {
+            self.settings_applied = false;
+            self.save_settings();
+        }
+        if !self.settings_applied {
+            self.settings.apply(ctx);
+            self.settings_applied = true;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.go_back();
+        }
+        if let Some(merged) = self.sync.poll(ctx, &self.profile_id) {
+            // Answers given while the sync was running are kept.
+            self.spaced_rep.merge(&merged);
+            self.save_progress();
+        }
+        if self.sync.account.is_some() && !self.locked {
+            // Nothing is sent while saved progress couldn't be loaded, as
+            // saving is off then too.
+            let progress = self.load_error.is_none().then_some(&self.spaced_rep);
+            self.sync.auto_sync(progress);
+            ctx.request_repaint_after(SYNC_INTERVAL);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
+            if self.profile_screen.open {
+                let open_id = (!self.locked).then_some(self.profile_id.as_str());
+                let picked = self.profile_screen.ui(ui, &mut self.profiles, open_id);
+                if let Some(id) = picked {
+                    self.switch_profile(id);
+                }
+                return;
+            }
+
+            if let Some(error) = &self.load_error {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Your saved progress couldn't be loaded, so nothing will be saved \
+                         this time. {}",
+                        error
+                    ))
+                    .color(self.settings.theme.wrong_colour()),
+                );
+            }
+            if let Some(notice) = &self.recovery_notice {
+                ui.label(notice);
+                if ui.small_button("OK").clicked() {
+                    self.recovery_notice = None;
+                }
+            }
+
+            if self.sprint.has_result() {
+                self.sprint_result_ui(ui);
+                return;
+            }
+            if self.sprint.is_running() {
+                self.sprint_ui(ui);
+                return;
+            }
+            if self.grid.open {
+                let locale = self.settings.locale;
+                if let Some(fact) = self.grid.ui(ui, &self.spaced_rep, &locale) {
+                    self.practise_fact(fact);
+                }
+                return;
+            }
+            if self.stats.open {
+                self.stats.ui(ui, &self.spaced_rep);
+                return;
+            }
+            if self.settings_screen.open {
+                self.settings_ui(ui);
+                return;
+            }
+
             ui.vertical_centered(|ui| {
                 ui.add_space(20.0);
                 ui.heading("Times Tables Practice");
-                ui.add_space(30.0);
+                match &self.focus {
+                    Some(focus) => {
+                        ui.add_space(4.0);
+                        ui.label(format!("Focus: {}", focus.label()));
+                        ui.add_space(10.0);
+                    }
+                    None => ui.add_space(30.0),
+                }
 
                 match &self.current_problem {
                     Some(problem) => {
-                        ui.label(
-                            egui::RichText::new(problem.display())
+                        let question = ui.label(
+                            egui::RichText::new(self.settings.locale.question(problem))
                                 .size(48.0)
                                 .strong(),
                         );
+                        let mut spoken = self.settings.locale.question_words(problem);
+                        if let Some(last) = &self.answered_right {
+                            spoken = format!(
+                                "Right: {}. {}",
+                                self.settings.locale.equation(last),
+                                spoken
+                            );
+                        }
+                        access::announce(&question, &spoken, Live::Polite);
+                        if self.settings.show_words {
+                            ui.label(
+                                egui::RichText::new(self.settings.locale.question_words(problem))
+                                    .size(18.0)
+                                    .weak(),
+                            );
+                        }
+                        if self.settings.show_visual && self.feedback == FeedbackState::None {
+                            ui.add_space(10.0);
+                            visual::show(ui, &Visual::new(problem), false);
+                        }
                         ui.add_space(20.0);
 
                         match &self.feedback {
+                            FeedbackState::None if self.choices.is_some() => {
+                                if let Some(choice) =
+                                    self.choice_buttons(ui, !self.picker_open())
+                                {
+                                    self.pick_choice(choice);
+                                }
+                            }
                             FeedbackState::None => {
-                                let response = ui.add(
-                                    egui::TextEdit::singleline(&mut self.answer_input)
-                                        .hint_text("Enter answer")
-                                        .font(egui::TextStyle::Heading)
-                                        .desired_width(150.0)
-                                        .horizontal_align(egui::Align::Center),
-                                );
+                                let response = ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut self.answer_input)
+                                            .hint_text("Enter answer")
+                                            .font(egui::TextStyle::Heading)
+                                            .desired_width(150.0)
+                                            .horizontal_align(egui::Align::Center),
+                                    )
+                                    .labelled_by(question.id);
 
                                 if response.lost_focus()
                                     && ui.input(|i| i.key_pressed(egui::Key::Enter))
@@ -154,7 +657,10 @@ impl eframe::App for TimesTablesApp {
                                     self.submit_answer();
                                 }
 
-                                response.request_focus();
+                                if !self.picker_open() {
+                                    access::keep_focus(&response);
+                                }
+                                self.answer_error_label(ui);
 
                                 ui.add_space(15.0);
 
@@ -165,24 +671,44 @@ impl eframe::App for TimesTablesApp {
                                     self.submit_answer();
                                 }
                             }
-                            FeedbackState::Incorrect { correct_answer, user_answer } => {
-                                ui.label(
+                            FeedbackState::Incorrect {
+                                correct_answer,
+                                user_answer,
+                                mistake,
+                                hint,
+                            } => {
+                                let feedback = ui.label(
                                     egui::RichText::new(format!(
-                                        "{} is wrong. Type the answer: {}",
+                                        "✗ {} is wrong. Type the answer: {}",
                                         user_answer, correct_answer
                                     ))
                                     .size(24.0)
-                                    .color(egui::Color32::from_rgb(220, 20, 60)),
+                                    .color(self.settings.theme.wrong_colour()),
+                                );
+                                let spoken = format!(
+                                    "{} is wrong. {}. Type the answer: {}. Tip: {}",
+                                    user_answer,
+                                    mistake.describe(),
+                                    correct_answer,
+                                    hint
                                 );
+                                access::announce(&feedback, &spoken, Live::Assertive);
+                                ui.label(egui::RichText::new(mistake.describe()).size(16.0).weak());
+                                ui.add_space(8.0);
+                                ui.label(egui::RichText::new(format!("Tip: {}", hint)).size(18.0));
+                                ui.add_space(8.0);
+                                visual::show(ui, &Visual::new(problem), true);
                                 ui.add_space(15.0);
 
-                                let response = ui.add(
-                                    egui::TextEdit::singleline(&mut self.answer_input)
-                                        .hint_text(correct_answer.to_string())
-                                        .font(egui::TextStyle::Heading)
-                                        .desired_width(150.0)
-                                        .horizontal_align(egui::Align::Center),
-                                );
+                                let response = ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut self.answer_input)
+                                            .hint_text(correct_answer.to_string())
+                                            .font(egui::TextStyle::Heading)
+                                            .desired_width(150.0)
+                                            .horizontal_align(egui::Align::Center),
+                                    )
+                                    .labelled_by(feedback.id);
 
                                 if response.lost_focus()
                                     && ui.input(|i| i.key_pressed(egui::Key::Enter))
@@ -190,15 +716,38 @@ impl eframe::App for TimesTablesApp {
                                     self.check_correction();
                                 }
 
-                                response.request_focus();
+                                if !self.picker_open() {
+                                    access::keep_focus(&response);
+                                }
+                                self.answer_error_label(ui);
+
+                                ui.add_space(10.0);
+                                if self.spaced_rep.can_undo()
+                                    && ui
+                                        .button("Oops, typo")
+                                        .on_hover_text("Take this answer back and try again")
+                                        .clicked()
+                                {
+                                    self.undo_answer();
+                                }
                             }
                         }
+                        if let Some(last) = &self.answered_right {
+                            ui.add_space(10.0);
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "✓ Right: {}",
+                                    self.settings.locale.equation(last)
+                                ))
+                                .color(self.settings.theme.right_colour()),
+                            );
+                        }
                     }
                     None => {
                         ui.label(
                             egui::RichText::new("All mastered!")
                                 .size(32.0)
-                                .color(egui::Color32::from_rgb(50, 205, 50)),
+                                .color(self.settings.theme.right_colour()),
                         );
                         ui.add_space(10.0);
                         ui.label("Congratulations! You've mastered all times tables!");
@@ -211,7 +760,10 @@ impl eframe::App for TimesTablesApp {
             ui.add_space(10.0);
 
             ui.horizontal(|ui| {
-                ui.label(format!("Streak: {}", self.streak));
+                ui.label(format!(
+                    "Streak: {} (best {})",
+                    self.session.streak, self.session.best_streak
+                ));
                 ui.separator();
                 ui.label(format!(
                     "Mastered: {}/{}",
@@ -224,6 +776,27 @@ impl eframe::App for TimesTablesApp {
 
             ui.add_space(5.0);
 
+            let daily = self.spaced_rep.daily_summary();
+            ui.horizontal(|ui| {
+                let today = format!("Today: {}/{}", daily.done, daily.target);
+                if daily.met {
+                    ui.label(
+                        egui::RichText::new(format!("{} ✔", today))
+                            .color(self.settings.theme.right_colour()),
+                    );
+                } else {
+                    ui.label(today);
+                }
+                ui.separator();
+                ui.label(format!("Day streak: {}", daily.day_streak));
+                if daily.freezes > 0 {
+                    ui.separator();
+                    ui.label(format!("Freezes: {}", daily.freezes));
+                }
+            });
+
+            ui.add_space(5.0);
+
             ui.horizontal(|ui| {
                 ui.label(format!(
                     "Tables: {}",
@@ -240,7 +813,7 @@ impl eframe::App for TimesTablesApp {
             ui.horizontal(|ui| {
                 ui.label(format!(
                     "Session: {} correct, {} wrong",
-                    self.session_correct, self.session_wrong
+                    self.session.correct, self.session.wrong
                 ));
                 ui.separator();
                 ui.label(format!(
@@ -252,13 +825,97 @@ impl eframe::App for TimesTablesApp {
 
             ui.add_space(15.0);
 
+            if self.focus_picker.open {
+                let focused = self.focus.is_some();
+                match self.focus_picker.ui(ui, self.settings.theme, focused) {
+                    Some(FocusAction::Start(focus)) => self.start_focus(focus),
+                    Some(FocusAction::Clear) => self.clear_focus(),
+                    None => {}
+                }
+                ui.add_space(10.0);
+            } else if self.worksheet.open {
+                self.worksheet.ui(ui, &self.spaced_rep);
+                ui.add_space(10.0);
+            } else if self.sync.open {
+                let progress = self.load_error.is_none().then_some(&self.spaced_rep);
+                self.sync.ui(ui, &self.profile_id, progress);
+                ui.add_space(10.0);
+            } else {
+                ui.horizontal_wrapped(|ui| {
+                    let name = self.current_profile().map(|p| p.name.clone());
+                    if ui
+                        .small_button(format!("{}…", name.unwrap_or_default()))
+                        .on_hover_text("Switch learner")
+                        .clicked()
+                    {
+                        self.show_profile_screen();
+                    }
+                    if ui.small_button("Progress grid…").clicked() {
+                        self.grid.open = true;
+                    }
+                    if ui.small_button("Statistics…").clicked() {
+                        self.stats.open = true;
+                    }
+                    let modes = self.settings.modes;
+                    if modes.focus && ui.small_button("Focus practice…").clicked() {
+                        self.focus_picker.open = true;
+                    }
+                    if modes.worksheets && ui.small_button("Print worksheet…").clicked() {
+                        self.worksheet.show();
+                    }
+                    if ui.small_button("Sync…").clicked() {
+                        self.sync.open = true;
+                    }
+                    if ui.small_button("Settings…").clicked() {
+                        self.settings_screen.open = true;
+                    }
+                    if modes.sprints {
+                        ui.separator();
+                        if let Some(limit) = sprint::start_buttons(ui) {
+                            self.start_sprint(limit);
+                        }
+                    }
+                });
+            }
+
+            let settings_before = self.settings.clone();
+            ui.horizontal(|ui| {
+                if self.settings.modes.multiple_choice {
+                    let mut multiple_choice =
+                        self.settings.answer_mode == AnswerMode::MultipleChoice;
+                    if ui.checkbox(&mut multiple_choice, "Multiple choice").changed() {
+                        self.settings.answer_mode = if multiple_choice {
+                            AnswerMode::MultipleChoice
+                        } else {
+                            AnswerMode::Typed
+                        };
+                        self.deal_choices();
+                    }
+                }
+                ui.checkbox(&mut self.settings.show_visual, "Show dots");
+                ui.checkbox(&mut self.settings.show_words, "Words");
+            });
+            if self.settings != settings_before {
+                self.save_settings();
+            }
+
+            ui.add_space(5.0);
+
             if self.confirm_reset {
                 ui.horizontal(|ui| {
-                    ui.label("Reset all progress?");
-                    if ui.button("Yes, reset").clicked() {
+                    let question = ui.label("Reset all progress?");
+                    access::announce(&question, "Reset all progress?", Live::Assertive);
+                    let yes = ui.button("Yes, reset");
+                    let cancel = ui.button("Cancel");
+                    // Keep the keyboard on the two answers, starting on the safe one.
+                    let focused = ui.memory(|m| m.focused());
+                    if focused != Some(yes.id) && focused != Some(cancel.id) {
+                        cancel.request_focus();
+                    }
+                    if yes.clicked() {
                         self.reset_progress();
                     }
-                    if ui.button("Cancel").clicked() {
+                    if cancel.clicked() {
                         self.confirm_reset = false;
                     }
                 });
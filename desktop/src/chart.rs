@@ -0,0 +1,359 @@
+use crate::access;
+use chrono::{Datelike, Days, NaiveDate};
+use eframe::egui;
+use std::collections::HashMap;
+use tt_core::spaced_rep::SpacedRepetition;
+use tt_core::stats::DayStats;
+
+/// Days of practice plotted on the statistics charts.
+const CHART_DAYS: usize = 30;
+const CALENDAR_WEEKS: u32 = 12;
+const CHART_HEIGHT: f32 = 80.0;
+/// Room left of a chart for its scale.
+const SCALE_WIDTH: f32 = 40.0;
+/// Room under a chart for its labels.
+const LABEL_HEIGHT: f32 = 14.0;
+const LABEL_SIZE: f32 = 10.0;
+const DOT_RADIUS: f32 = 2.5;
+const LINE_COLOUR: egui::Color32 = egui::Color32::from_rgb(37, 99, 235);
+const BAR_GAP: f32 = 4.0;
+/// Bars for tables that aren't unlocked yet are drawn faded.
+const FADED_ALPHA: f32 = 0.35;
+const DAY_SIZE: f32 = 12.0;
+const DAY_GAP: f32 = 2.0;
+const GOAL_COLOUR: egui::Color32 = egui::Color32::from_rgb(22, 163, 74);
+const PRACTISED_COLOUR: egui::Color32 = egui::Color32::from_rgb(134, 239, 172);
+const FROZEN_COLOUR: egui::Color32 = egui::Color32::from_rgb(96, 165, 250);
+
+/// One point of a line chart. Points without a value leave a gap.
+struct Point {
+    label: String,
+    value: Option<f64>,
+}
+
+/// One bar of a bar chart, with its height as a share of the full height.
+struct Bar {
+    label: String,
+    value: Option<f64>,
+    faded: bool,
+    /// Shown when pointed at.
+    detail: String,
+}
+
+fn label_font() -> egui::FontId {
+    egui::FontId::proportional(LABEL_SIZE)
+}
+
+/// Splits the space for a chart into the plot and its labels.
+fn allocate(ui: &mut egui::Ui) -> (egui::Response, egui::Rect) {
+    let size = egui::vec2(ui.available_width(), CHART_HEIGHT + LABEL_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let plot = egui::Rect::from_min_max(
+        rect.min + egui::vec2(SCALE_WIDTH, 0.0),
+        rect.max - egui::vec2(0.0, LABEL_HEIGHT),
+    );
+    (response, plot)
+}
+
+fn draw_axes(ui: &egui::Ui, plot: egui::Rect, top: &str) {
+    let painter = ui.painter();
+    let colour = ui.visuals().weak_text_color();
+    let stroke = egui::Stroke::new(1.0, colour);
+    painter.line_segment([plot.left_bottom(), plot.right_bottom()], stroke);
+    painter.line_segment([plot.left_top(), plot.left_bottom()], stroke);
+    for (text, y) in [(top, plot.top()), ("0", plot.bottom())] {
+        painter.text(
+            egui::pos2(plot.left() - 4.0, y),
+            egui::Align2::RIGHT_CENTER,
+            text,
+            label_font(),
+            colour,
+        );
+    }
+}
+
+/// Plots `points` evenly spaced, from 0 at the bottom to `max` at the top,
+/// joining neighbouring values. `format` writes a value for the scale, for
+/// the point under the pointer and for screen readers, which get every point.
+fn line(ui: &mut egui::Ui, points: &[Point], max: f64, format: impl Fn(f64) -> String) {
+    if points.is_empty() {
+        ui.label(egui::RichText::new("No practice yet").weak());
+        return;
+    }
+    let max = if max > 0.0 { max } else { 1.0 };
+    let (response, plot) = allocate(ui);
+    draw_axes(ui, plot, &format(max));
+    let spoken: Vec<String> = points
+        .iter()
+        .map(|p| {
+            format!(
+                "{}: {}",
+                p.label,
+                p.value.map_or("no answers".to_string(), &format)
+            )
+        })
+        .collect();
+    access::describe(&response, &spoken.join(", "));
+
+    let step = plot.width() / (points.len().max(2) - 1) as f32;
+    let x = |i: usize| {
+        if points.len() == 1 {
+            plot.center().x
+        } else {
+            plot.left() + i as f32 * step
+        }
+    };
+    let y = |v: f64| plot.bottom() - (v / max).clamp(0.0, 1.0) as f32 * plot.height();
+
+    let painter = ui.painter();
+    let stroke = egui::Stroke::new(2.0, LINE_COLOUR);
+    for (i, pair) in points.windows(2).enumerate() {
+        if let (Some(a), Some(b)) = (pair[0].value, pair[1].value) {
+            painter.line_segment([egui::pos2(x(i), y(a)), egui::pos2(x(i + 1), y(b))], stroke);
+        }
+    }
+    for (i, point) in points.iter().enumerate() {
+        if let Some(v) = point.value {
+            painter.circle_filled(egui::pos2(x(i), y(v)), DOT_RADIUS, LINE_COLOUR);
+        }
+    }
+
+    let colour = ui.visuals().weak_text_color();
+    let label_y = plot.bottom() + 2.0;
+    painter.text(
+        egui::pos2(plot.left(), label_y),
+        egui::Align2::LEFT_TOP,
+        &points[0].label,
+        label_font(),
+        colour,
+    );
+    if let [_, .., last] = points {
+        painter.text(
+            egui::pos2(plot.right(), label_y),
+            egui::Align2::RIGHT_TOP,
+            &last.label,
+            label_font(),
+            colour,
+        );
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let i = if points.len() == 1 {
+            0
+        } else {
+            (((pos.x - plot.left()) / step).round().max(0.0) as usize).min(points.len() - 1)
+        };
+        let point = &points[i];
+        painter.line_segment(
+            [
+                egui::pos2(x(i), plot.top()),
+                egui::pos2(x(i), plot.bottom()),
+            ],
+            egui::Stroke::new(1.0, colour),
+        );
+        let value = point.value.map_or("no answers".to_string(), &format);
+        response.on_hover_text_at_pointer(format!("{}: {}", point.label, value));
+    }
+}
+
+/// A row of bars from 0 at the bottom to 1 at the top, each with its label
+/// underneath and its detail when pointed at.
+fn bars(ui: &mut egui::Ui, bars: &[Bar], top: &str) {
+    let (_, plot) = allocate(ui);
+    draw_axes(ui, plot, top);
+
+    let width = plot.width() / bars.len().max(1) as f32;
+    let colour = ui.visuals().weak_text_color();
+    for (i, bar) in bars.iter().enumerate() {
+        let column = egui::Rect::from_min_size(
+            egui::pos2(plot.left() + i as f32 * width, plot.top()),
+            egui::vec2(width, plot.height()),
+        );
+        if let Some(value) = bar.value {
+            let height = value.clamp(0.0, 1.0) as f32 * plot.height();
+            let rect = egui::Rect::from_min_max(
+                egui::pos2(column.left() + BAR_GAP / 2.0, column.bottom() - height),
+                egui::pos2(column.right() - BAR_GAP / 2.0, column.bottom()),
+            );
+            let fill = if bar.faded {
+                LINE_COLOUR.gamma_multiply(FADED_ALPHA)
+            } else {
+                LINE_COLOUR
+            };
+            ui.painter().rect_filled(rect, 2.0, fill);
+        }
+        ui.painter().text(
+            egui::pos2(column.center().x, plot.bottom() + 2.0),
+            egui::Align2::CENTER_TOP,
+            &bar.label,
+            label_font(),
+            colour,
+        );
+        let response = ui.interact(column, ui.id().with(("bar", i)), egui::Sense::hover());
+        access::describe(&response, &bar.detail);
+        response.on_hover_text(&bar.detail);
+    }
+}
+
+/// The last `weeks` weeks to `today`, a column per week with Monday at the
+/// top. Each day is shaded by whether the goal was met, the learner
+/// practised, or a streak freeze covered it.
+fn calendar(ui: &mut egui::Ui, days: &[DayStats], today: NaiveDate, weeks: u32) {
+    let by_date: HashMap<NaiveDate, &DayStats> = days.iter().map(|d| (d.date, d)).collect();
+    let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let first = this_monday - Days::new(7 * (weeks as u64 - 1));
+
+    let size = egui::vec2(
+        weeks as f32 * (DAY_SIZE + DAY_GAP),
+        7.0 * (DAY_SIZE + DAY_GAP),
+    );
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let empty = ui.visuals().widgets.inactive.bg_fill;
+
+    for offset in 0..weeks as u64 * 7 {
+        let date = first + Days::new(offset);
+        if date > today {
+            break;
+        }
+        let cell = egui::Rect::from_min_size(
+            rect.min
+                + egui::vec2(
+                    (offset / 7) as f32 * (DAY_SIZE + DAY_GAP),
+                    (offset % 7) as f32 * (DAY_SIZE + DAY_GAP),
+                ),
+            egui::vec2(DAY_SIZE, DAY_SIZE),
+        );
+        let day = by_date.get(&date);
+        let (fill, note) = match day {
+            Some(d) if d.goal_met => (GOAL_COLOUR, "goal met".to_string()),
+            Some(d) if d.answers() > 0 => (PRACTISED_COLOUR, String::new()),
+            Some(d) if d.frozen => (FROZEN_COLOUR, "streak freeze".to_string()),
+            _ => (empty, String::new()),
+        };
+        ui.painter().rect_filled(cell, 2.0, fill);
+
+        let answers = day.map_or(0, |d| d.answers());
+        let mut detail = format!("{}: {} answers", date.format("%a %d %b"), answers);
+        if !note.is_empty() {
+            detail = format!("{}, {}", detail, note);
+        }
+        let response = ui.interact(cell, ui.id().with(("day", offset)), egui::Sense::hover());
+        access::describe(&response, &detail);
+        response.on_hover_text(detail);
+    }
+
+    ui.horizontal(|ui| {
+        for (colour, label) in [
+            (GOAL_COLOUR, "Goal met"),
+            (PRACTISED_COLOUR, "Practised"),
+            (FROZEN_COLOUR, "Freeze"),
+        ] {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, colour);
+            ui.label(label);
+        }
+    });
+}
+
+/// Charts of how practice has gone, day by day and table by table.
+#[derive(Default)]
+pub struct StatsScreen {
+    pub open: bool,
+}
+
+impl StatsScreen {
+    pub fn ui(&mut self, ui: &mut egui::Ui, spaced_rep: &SpacedRepetition) {
+        let stats = spaced_rep.statistics();
+        let practised: Vec<&DayStats> = stats.days.iter().filter(|d| d.answers() > 0).collect();
+        let recent = &practised[practised.len().saturating_sub(CHART_DAYS)..];
+        let points = |value: &dyn Fn(&DayStats) -> Option<f64>| -> Vec<Point> {
+            recent
+                .iter()
+                .map(|day| Point {
+                    label: day.date.format("%d %b").to_string(),
+                    value: value(day),
+                })
+                .collect()
+        };
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.heading("Statistics");
+            let answers: u32 = practised.iter().map(|d| d.answers()).sum();
+            let minutes = practised.iter().map(|d| d.practice_secs).sum::<f64>() / 60.0;
+            ui.label(format!(
+                "{} days of practice, {} answers, {:.0} minutes in all",
+                practised.len(),
+                answers,
+                minutes
+            ));
+            if !recent.is_empty() {
+                ui.label(
+                    egui::RichText::new(format!("Charts show the last {} days", recent.len()))
+                        .weak(),
+                );
+            }
+
+            ui.add_space(10.0);
+            ui.strong("Accuracy");
+            line(
+                ui,
+                &points(&|d| d.accuracy().map(|a| a * 100.0)),
+                100.0,
+                |v| format!("{:.0}%", v),
+            );
+
+            ui.add_space(10.0);
+            ui.strong("Facts mastered");
+            let mastered = points(&|d| d.mastered.map(f64::from));
+            let most = mastered.iter().filter_map(|p| p.value).fold(0.0, f64::max);
+            line(ui, &mastered, most, |v| format!("{:.0}", v));
+
+            ui.add_space(10.0);
+            ui.strong("Average answer time");
+            let times = points(&|d| d.avg_response_secs());
+            let slowest = times.iter().filter_map(|p| p.value).fold(0.0, f64::max);
+            line(ui, &times, slowest, |v| format!("{:.1}s", v));
+
+            ui.add_space(10.0);
+            ui.strong("Tables");
+            let tables: Vec<Bar> = stats
+                .tables
+                .iter()
+                .map(|t| {
+                    let mut detail = format!(
+                        "{}× table: {} of {} facts mastered",
+                        t.table, t.mastered, t.facts
+                    );
+                    if let Some(accuracy) = t.accuracy() {
+                        detail += &format!(", {:.0}% right", accuracy * 100.0);
+                    }
+                    if let Some(secs) = t.avg_response_secs {
+                        detail += &format!(", {:.1}s per answer", secs);
+                    }
+                    if !t.unlocked {
+                        detail += " (not unlocked yet)";
+                    }
+                    Bar {
+                        label: t.table.to_string(),
+                        value: t.accuracy(),
+                        faded: !t.unlocked,
+                        detail,
+                    }
+                })
+                .collect();
+            bars(ui, &tables, "100%");
+            ui.label(egui::RichText::new("Share of answers right in each table").weak());
+
+            ui.add_space(10.0);
+            ui.strong("Practice calendar");
+            let today = chrono::Local::now().date_naive();
+            calendar(ui, &stats.days, today, CALENDAR_WEEKS);
+
+            ui.add_space(10.0);
+            if ui.button("Back to practice").clicked() {
+                self.open = false;
+            }
+        });
+    }
+}
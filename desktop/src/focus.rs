@@ -0,0 +1,85 @@
+use crate::settings::Theme;
+use eframe::egui;
+use tt_core::focus::Focus;
+
+/// What the learner chose on the focus picker.
+pub enum FocusAction {
+    Start(Focus),
+    /// Go back to practising every unlocked table.
+    Clear,
+}
+
+/// Picks tables and facts to practise on their own, shown under practice.
+#[derive(Default)]
+pub struct FocusPicker {
+    pub open: bool,
+    tables: Vec<u8>,
+    facts_input: String,
+    error: Option<String>,
+}
+
+impl FocusPicker {
+    pub fn close(&mut self) {
+        self.open = false;
+        self.error = None;
+    }
+
+    /// The focus picked so far, or why it can't be practised.
+    fn focus(&self) -> Result<Focus, String> {
+        let facts = Focus::parse_facts(&self.facts_input)
+            .ok_or_else(|| "Write facts like 6x7, 7x8".to_string())?;
+        let focus = Focus::new(self.tables.clone(), facts);
+        if focus.is_empty() {
+            return Err("Pick at least one table or fact".to_string());
+        }
+        Ok(focus)
+    }
+
+    /// `focused` says whether a focus is being practised, which can then be
+    /// cleared from here.
+    pub fn ui(&mut self, ui: &mut egui::Ui, theme: Theme, focused: bool) -> Option<FocusAction> {
+        ui.label("Tables:");
+        ui.horizontal_wrapped(|ui| {
+            for table in 1..=12u8 {
+                let selected = self.tables.contains(&table);
+                if ui
+                    .selectable_label(selected, format!("{}×", table))
+                    .clicked()
+                {
+                    if selected {
+                        self.tables.retain(|t| *t != table);
+                    } else {
+                        self.tables.push(table);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Facts:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.facts_input)
+                    .hint_text("6x7, 7x8, 8x6")
+                    .desired_width(180.0),
+            );
+        });
+        if let Some(error) = &self.error {
+            ui.label(egui::RichText::new(error).color(theme.wrong_colour()));
+        }
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if ui.button("Start focus").clicked() {
+                match self.focus() {
+                    Ok(focus) => action = Some(FocusAction::Start(focus)),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if focused && ui.button("Practise everything").clicked() {
+                action = Some(FocusAction::Clear);
+            }
+            if ui.button("Close").clicked() {
+                self.open = false;
+            }
+        });
+        action
+    }
+}
@@ -0,0 +1,289 @@
+use chrono::{Local, Utc};
+use eframe::egui;
+use tt_core::choice::AnswerMode;
+use tt_core::journal::JournalEntry;
+use tt_core::locale::Locale;
+use tt_core::problem::Problem;
+use tt_core::spaced_rep::SpacedRepetition;
+
+const CELL_SIZE: f32 = 22.0;
+const CELL_GAP: f32 = 2.0;
+/// Room for the row and column numbers.
+const HEADER_SIZE: f32 = 20.0;
+/// Height of the accuracy strip along the bottom of answered cells.
+const ACCURACY_HEIGHT: f32 = 4.0;
+const DUE_RADIUS: f32 = 3.0;
+const DUE_COLOUR: egui::Color32 = egui::Color32::from_rgb(250, 204, 21);
+const RIGHT_COLOUR: egui::Color32 = egui::Color32::from_rgb(50, 205, 50);
+const WRONG_COLOUR: egui::Color32 = egui::Color32::from_rgb(220, 20, 60);
+/// Cells of tables that aren't unlocked yet are drawn faded.
+const LOCKED_ALPHA: f32 = 0.35;
+/// Answers listed for a fact picked on the grid, newest first.
+const HISTORY_SHOWN: usize = 10;
+
+/// Each `grid_status` tier with its label, in the order of `tier_colours`.
+const TIERS: [(&str, &str); 5] = [
+    ("not_started", "Not started"),
+    ("learning", "Learning"),
+    ("solid", "Solid"),
+    ("fast", "Fast"),
+    ("mastered", "Mastered"),
+];
+
+/// Fill colours for the tiers, from not started to mastered, as on the
+/// website. On a dark background the two ends swap, so that mastered is the
+/// lightest cell rather than one lost in the background.
+fn tier_colours(visuals: &egui::Visuals) -> [egui::Color32; 5] {
+    let learning = egui::Color32::from_rgb(22, 163, 74);
+    let fast = egui::Color32::from_rgb(249, 115, 22);
+    if visuals.dark_mode {
+        [
+            egui::Color32::from_rgb(75, 85, 99),
+            learning,
+            egui::Color32::from_rgb(59, 130, 246),
+            fast,
+            egui::Color32::from_rgb(243, 244, 246),
+        ]
+    } else {
+        [
+            egui::Color32::from_rgb(209, 213, 219),
+            learning,
+            egui::Color32::from_rgb(37, 99, 235),
+            fast,
+            egui::Color32::from_rgb(17, 24, 39),
+        ]
+    }
+}
+
+fn tier(status: &str, visuals: &egui::Visuals) -> (&'static str, egui::Color32) {
+    let i = TIERS
+        .iter()
+        .position(|(name, _)| *name == status)
+        .unwrap_or(0);
+    (TIERS[i].1, tier_colours(visuals)[i])
+}
+
+/// Draws the 12×12 grid: each fact's tier as its colour, a dot when it is
+/// due, and a strip of right against wrong answers. Hovering a cell gives the
+/// details; returns the fact whose cell was clicked.
+fn show(
+    ui: &mut egui::Ui,
+    spaced_rep: &SpacedRepetition,
+    locale: &Locale,
+    selected: Option<Problem>,
+) -> Option<Problem> {
+    let side = HEADER_SIZE + 12.0 * (CELL_SIZE + CELL_GAP);
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let text_colour = ui.visuals().weak_text_color();
+    let now = Utc::now();
+
+    let cell_origin = |n: u8| HEADER_SIZE + (n - 1) as f32 * (CELL_SIZE + CELL_GAP);
+    for n in 1..=12u8 {
+        let middle = cell_origin(n) + CELL_SIZE / 2.0;
+        for offset in [
+            egui::vec2(middle, HEADER_SIZE / 2.0),
+            egui::vec2(HEADER_SIZE / 2.0, middle),
+        ] {
+            painter.text(
+                rect.min + offset,
+                egui::Align2::CENTER_CENTER,
+                n.to_string(),
+                egui::FontId::proportional(11.0),
+                text_colour,
+            );
+        }
+    }
+
+    let mut clicked = None;
+    for (i, status) in spaced_rep.grid_status().into_iter().enumerate() {
+        let problem = Problem::new(i as u8 / 12 + 1, i as u8 % 12 + 1);
+        let Some(stats) = spaced_rep.stats_for(&problem) else {
+            continue;
+        };
+        let unlocked = spaced_rep.is_unlocked(&problem);
+        let cell = egui::Rect::from_min_size(
+            rect.min + egui::vec2(cell_origin(problem.b), cell_origin(problem.a)),
+            egui::vec2(CELL_SIZE, CELL_SIZE),
+        );
+        let (label, colour) = tier(status, ui.visuals());
+        let fade = |c: egui::Color32| {
+            if unlocked {
+                c
+            } else {
+                c.gamma_multiply(LOCKED_ALPHA)
+            }
+        };
+
+        painter.rect_filled(cell, 3.0, fade(colour));
+        let answers = stats.times_correct + stats.times_wrong;
+        if answers > 0 {
+            let accuracy = stats.times_correct as f32 / answers as f32;
+            let strip = egui::Rect::from_min_max(
+                egui::pos2(cell.left(), cell.bottom() - ACCURACY_HEIGHT),
+                cell.max,
+            );
+            painter.rect_filled(strip, 0.0, fade(WRONG_COLOUR));
+            let right = egui::Rect::from_min_size(
+                strip.min,
+                egui::vec2(strip.width() * accuracy, strip.height()),
+            );
+            painter.rect_filled(right, 0.0, fade(RIGHT_COLOUR));
+        }
+        let due = unlocked && stats.is_due_at(now);
+        if due {
+            let centre = cell.right_top() + egui::vec2(-DUE_RADIUS - 2.0, DUE_RADIUS + 2.0);
+            painter.circle_filled(centre, DUE_RADIUS, DUE_COLOUR);
+        }
+        if selected == Some(problem) {
+            painter.rect_stroke(cell, 3.0, ui.visuals().selection.stroke);
+        }
+
+        let mut details = vec![locale.equation(&problem), label.to_string()];
+        if answers > 0 {
+            details.push(format!(
+                "{} right, {} wrong ({:.0}%)",
+                stats.times_correct,
+                stats.times_wrong,
+                stats.times_correct as f32 / answers as f32 * 100.0
+            ));
+        }
+        details.push(if !unlocked {
+            "Table not unlocked yet".to_string()
+        } else if due {
+            "Due now".to_string()
+        } else {
+            format!(
+                "Next review {}",
+                stats
+                    .next_review
+                    .with_timezone(&Local)
+                    .format("%d %b %H:%M")
+            )
+        });
+
+        // Cells take the keyboard like buttons, and read out their details.
+        let spoken = details.join(", ");
+        let response = ui.interact(cell, ui.id().with(("grid_cell", i)), egui::Sense::click());
+        response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, true, &spoken));
+        let response = response.on_hover_text(details.join("\n"));
+        if response.has_focus() {
+            painter.rect_stroke(
+                cell.expand(1.0),
+                3.0,
+                ui.visuals().widgets.hovered.fg_stroke,
+            );
+        }
+        if response.clicked() {
+            clicked = Some(problem);
+        }
+    }
+    clicked
+}
+
+/// A key to the cell colours and marks.
+fn legend(ui: &mut egui::Ui) {
+    ui.horizontal_wrapped(|ui| {
+        let colours = tier_colours(ui.visuals());
+        for ((_, label), colour) in TIERS.into_iter().zip(colours) {
+            swatch(ui, |rect| egui::Shape::rect_filled(rect, 2.0, colour));
+            ui.label(label);
+        }
+        swatch(ui, |rect| {
+            egui::Shape::circle_filled(rect.center(), DUE_RADIUS, DUE_COLOUR)
+        });
+        ui.label("Due");
+    });
+}
+
+fn swatch(ui: &mut egui::Ui, shape: impl FnOnce(egui::Rect) -> egui::Shape) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+    ui.painter().add(shape(rect));
+}
+
+/// The progress grid, with the answers given to the fact picked on it.
+#[derive(Default)]
+pub struct GridScreen {
+    pub open: bool,
+    /// The fact picked on the grid, whose answers are listed.
+    fact: Option<Problem>,
+}
+
+impl GridScreen {
+    /// Returns the fact to practise next, if the learner asked for one.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        spaced_rep: &SpacedRepetition,
+        locale: &Locale,
+    ) -> Option<Problem> {
+        let mut practise = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.heading("Progress");
+            ui.add_space(10.0);
+            if let Some(fact) = show(ui, spaced_rep, locale, self.fact) {
+                self.fact = Some(fact);
+            }
+            legend(ui);
+            ui.label(
+                egui::RichText::new("Point at a fact for details, or click it for its answers")
+                    .weak(),
+            );
+
+            if let Some(fact) = self.fact {
+                ui.add_space(5.0);
+                ui.separator();
+                if fact_history(ui, spaced_rep, locale, fact) {
+                    practise = Some(fact);
+                }
+            }
+
+            ui.add_space(10.0);
+            if ui.button("Back to practice").clicked() {
+                self.open = false;
+            }
+        });
+        practise
+    }
+}
+
+/// Lists the latest answers to `fact`. Returns true if the learner asked to
+/// practise it.
+fn fact_history(
+    ui: &mut egui::Ui,
+    spaced_rep: &SpacedRepetition,
+    locale: &Locale,
+    fact: Problem,
+) -> bool {
+    let practise = ui
+        .horizontal(|ui| {
+            ui.strong(locale.equation(&fact));
+            ui.button("Practise now").clicked()
+        })
+        .inner;
+
+    let history: Vec<&JournalEntry> = spaced_rep.history_for(&fact).collect();
+    if history.is_empty() {
+        ui.label("No answers yet");
+        return practise;
+    }
+    for entry in history.iter().rev().take(HISTORY_SHOWN) {
+        let result = match (entry.correct, entry.mistake) {
+            (true, _) if entry.mode == AnswerMode::MultipleChoice => "right (picked)",
+            (true, _) => "right",
+            (false, Some(mistake)) if mistake.is_slip() => "slip",
+            (false, _) => "wrong",
+        };
+        ui.label(format!(
+            "{}  {}  {:.1}s",
+            entry
+                .answered_at
+                .with_timezone(&Local)
+                .format("%d %b %H:%M"),
+            result,
+            entry.response_secs
+        ));
+    }
+    practise
+}
@@ -1,14 +1,25 @@
+mod access;
 mod app;
+mod chart;
+mod focus;
+mod grid;
+mod profile;
+mod settings;
+mod sprint;
 mod storage;
+mod sync;
+mod visual;
+mod worksheet;
 
 use app::TimesTablesApp;
 use eframe::egui;
+use settings::{MIN_WINDOW_SIZE, WINDOW_SIZE};
 
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([400.0, 420.0])
-            .with_min_inner_size([350.0, 380.0]),
+            .with_inner_size(WINDOW_SIZE)
+            .with_min_inner_size(MIN_WINDOW_SIZE),
         ..Default::default()
     };
 
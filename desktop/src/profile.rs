@@ -0,0 +1,313 @@
+//! Learners sharing the app on one computer.
+//!
+//! Each profile has its own folder for progress, settings and server account,
+//! so siblings don't overwrite each other. A PIN keeps a profile from being
+//! opened by mistake; it isn't meant to stand up to a determined sibling.
+
+use crate::{access, storage};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+/// Name given to the profile that progress from before profiles moves into.
+pub const DEFAULT_NAME: &str = "Learner";
+const MAX_NAME_CHARS: usize = 24;
+const PIN_DIGITS: std::ops::RangeInclusive<usize> = 4..=8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Also the name of the profile's folder.
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pin_hash: Option<String>,
+}
+
+impl Profile {
+    pub fn new(id: String, name: &str) -> Self {
+        Self {
+            id,
+            name: name.trim().to_string(),
+            pin_hash: None,
+        }
+    }
+
+    pub fn has_pin(&self) -> bool {
+        self.pin_hash.is_some()
+    }
+
+    /// Sets the PIN, or removes it when `pin` is blank.
+    pub fn set_pin(&mut self, pin: &str) -> Result<(), String> {
+        let pin = pin.trim();
+        if pin.is_empty() {
+            self.pin_hash = None;
+            return Ok(());
+        }
+        if !PIN_DIGITS.contains(&pin.len()) || !pin.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!(
+                "A PIN is {} to {} digits",
+                PIN_DIGITS.start(),
+                PIN_DIGITS.end()
+            ));
+        }
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(pin.as_bytes(), &salt)
+            .map_err(|e| format!("Failed to set PIN: {}", e))?;
+        self.pin_hash = Some(hash.to_string());
+        Ok(())
+    }
+
+    /// Whether `pin` opens the profile. Always true without a PIN.
+    pub fn check_pin(&self, pin: &str) -> bool {
+        let Some(stored) = &self.pin_hash else {
+            return true;
+        };
+        PasswordHash::new(stored).is_ok_and(|parsed| {
+            Argon2::default()
+                .verify_password(pin.trim().as_bytes(), &parsed)
+                .is_ok()
+        })
+    }
+}
+
+/// Checks a name for a new profile against the existing ones.
+pub fn check_name(name: &str, profiles: &[Profile]) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Type a name first".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("Names can be up to {} letters", MAX_NAME_CHARS));
+    }
+    if profiles.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+        return Err(format!("There is already a profile called {}", name));
+    }
+    Ok(())
+}
+
+/// The first free id: "1", "2" and so on.
+pub fn next_id(profiles: &[Profile]) -> String {
+    (1u32..)
+        .map(|n| n.to_string())
+        .find(|id| !profiles.iter().any(|p| &p.id == id))
+        .unwrap_or_default()
+}
+
+/// Picking who's practising, adding learners and setting PINs.
+#[derive(Default)]
+pub struct ProfileScreen {
+    pub open: bool,
+    /// A profile picked that is waiting for its PIN.
+    unlocking: Option<String>,
+    pin: String,
+    new_name: String,
+    new_pin: String,
+    /// A new PIN for the open profile; blank removes it.
+    pin_change: String,
+    status: Option<String>,
+}
+
+impl ProfileScreen {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.unlocking = None;
+        self.pin.clear();
+        self.pin_change.clear();
+        self.status = None;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.unlocking = None;
+        self.pin.clear();
+        self.status = None;
+    }
+
+    /// Stops asking for a PIN, or leaves the screen if a profile is open.
+    pub fn back(&mut self, locked: bool) {
+        if self.unlocking.is_some() {
+            self.unlocking = None;
+            self.pin.clear();
+        } else if !locked {
+            self.open = false;
+        }
+        self.status = None;
+    }
+
+    /// Asks for the PIN of a profile that has one. Going back to the open
+    /// profile doesn't ask again. Returns the profile to open.
+    fn pick(&mut self, id: String, profiles: &[Profile], open_id: Option<&str>) -> Option<String> {
+        let needs_pin = profiles.iter().any(|p| p.id == id && p.has_pin());
+        if needs_pin && open_id != Some(id.as_str()) {
+            self.unlocking = Some(id);
+            self.pin.clear();
+            self.status = None;
+            return None;
+        }
+        Some(id)
+    }
+
+    fn unlock(&mut self, profiles: &[Profile]) -> Option<String> {
+        let id = self.unlocking.clone()?;
+        if !profiles
+            .iter()
+            .any(|p| p.id == id && p.check_pin(&self.pin))
+        {
+            self.pin.clear();
+            self.status = Some("That PIN isn't right".to_string());
+            return None;
+        }
+        Some(id)
+    }
+
+    fn add(&mut self, profiles: &mut Vec<Profile>) -> Option<String> {
+        if let Err(e) = check_name(&self.new_name, profiles) {
+            self.status = Some(e);
+            return None;
+        }
+        let mut new = Profile::new(next_id(profiles), &self.new_name);
+        if let Err(e) = new
+            .set_pin(&self.new_pin)
+            .and_then(|()| storage::save_profile(&new))
+        {
+            self.status = Some(e);
+            return None;
+        }
+        let id = new.id.clone();
+        profiles.push(new);
+        profiles.sort_by_key(|p| p.name.to_lowercase());
+        self.new_name.clear();
+        self.new_pin.clear();
+        Some(id)
+    }
+
+    /// Sets the open profile's PIN, or removes it when left blank.
+    fn change_pin(&mut self, profile: &mut Profile) {
+        let pin = std::mem::take(&mut self.pin_change);
+        let result = profile
+            .set_pin(&pin)
+            .and_then(|()| storage::save_profile(profile));
+        self.status = Some(match result {
+            Ok(()) if profile.has_pin() => "PIN saved".to_string(),
+            Ok(()) => "PIN removed".to_string(),
+            Err(e) => e,
+        });
+    }
+
+    /// `open_id` is the profile that's open, or `None` while the app is
+    /// locked. Returns the profile to open instead.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        profiles: &mut Vec<Profile>,
+        open_id: Option<&str>,
+    ) -> Option<String> {
+        let mut opened = None;
+        ui.vertical_centered(|ui| {
+            ui.add_space(20.0);
+            ui.heading("Who's practising?");
+            ui.add_space(15.0);
+
+            let mut picked = None;
+            for profile in profiles.iter() {
+                let label = if profile.has_pin() {
+                    format!("{} (PIN)", profile.name)
+                } else {
+                    profile.name.clone()
+                };
+                let open = open_id == Some(profile.id.as_str());
+                if ui
+                    .add_sized([200.0, 36.0], egui::SelectableLabel::new(open, label))
+                    .clicked()
+                {
+                    picked = Some(profile.id.clone());
+                }
+            }
+            if let Some(id) = picked {
+                opened = self.pick(id, profiles, open_id);
+            }
+
+            let unlocking = self
+                .unlocking
+                .as_ref()
+                .and_then(|id| profiles.iter().find(|p| &p.id == id))
+                .map(|p| p.name.clone());
+            if let Some(name) = unlocking {
+                ui.add_space(10.0);
+                let label = ui.label(format!("PIN for {}:", name));
+                let response = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.pin)
+                            .password(true)
+                            .desired_width(120.0)
+                            .horizontal_align(egui::Align::Center),
+                    )
+                    .labelled_by(label.id);
+                let entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                access::keep_focus(&response);
+                if ui.button("Open").clicked() || entered {
+                    opened = self.unlock(profiles);
+                }
+            }
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+        });
+
+        ui.add_space(20.0);
+        ui.separator();
+        ui.label("New learner:");
+        egui::Grid::new("new_profile")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let label = ui.label("Name:");
+                ui.add(egui::TextEdit::singleline(&mut self.new_name).desired_width(160.0))
+                    .labelled_by(label.id);
+                ui.end_row();
+                let label = ui.label("PIN:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_pin)
+                        .password(true)
+                        .hint_text("optional")
+                        .desired_width(160.0),
+                )
+                .labelled_by(label.id);
+                ui.end_row();
+            });
+        if ui.button("Add learner").clicked() {
+            opened = self.add(profiles);
+        }
+
+        let Some(open_id) = open_id else {
+            return opened;
+        };
+        ui.add_space(10.0);
+        ui.separator();
+        ui.horizontal(|ui| {
+            let label = ui.label("PIN for this profile:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pin_change)
+                    .password(true)
+                    .hint_text("blank removes it")
+                    .desired_width(120.0),
+            )
+            .labelled_by(label.id);
+            if ui.button("Save PIN").clicked() {
+                if let Some(profile) = profiles.iter_mut().find(|p| p.id == open_id) {
+                    self.change_pin(profile);
+                }
+            }
+        });
+        ui.add_space(10.0);
+        if ui.button("Back to practice").clicked() {
+            self.open = false;
+            self.status = None;
+        }
+        opened
+    }
+}
@@ -0,0 +1,360 @@
+//! Display and answering choices, kept per profile. Choices that change how
+//! facts are scheduled, such as the daily goal, are kept with the progress
+//! instead so they travel with it when syncing.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use tt_core::choice::AnswerMode;
+use tt_core::daily::DailyGoal;
+use tt_core::locale::{Language, Locale, Operator};
+use tt_core::problem::TimeThresholds;
+use tt_core::spaced_rep::SpacedRepetition;
+
+/// The window size at a text size of 1.
+pub const WINDOW_SIZE: egui::Vec2 = egui::vec2(400.0, 420.0);
+pub const MIN_WINDOW_SIZE: egui::Vec2 = egui::vec2(350.0, 380.0);
+pub const TEXT_SCALES: std::ops::RangeInclusive<f32> = 0.75..=2.0;
+/// How much Ctrl+Plus and Ctrl+Minus change the text size by.
+pub const TEXT_SCALE_STEP: f32 = 0.25;
+/// The daily goals the server accepts.
+const GOAL_TARGETS: std::ops::RangeInclusive<u32> = 1..=1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    /// White on black with bold outlines.
+    HighContrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+            Theme::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        match self {
+            Theme::Light => egui::Visuals::light(),
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::HighContrast => high_contrast(),
+        }
+    }
+
+    /// Text colour for right answers and goals met.
+    pub fn right_colour(&self) -> egui::Color32 {
+        match self {
+            Theme::HighContrast => egui::Color32::from_rgb(110, 255, 110),
+            _ => egui::Color32::from_rgb(50, 205, 50),
+        }
+    }
+
+    /// Text colour for wrong answers and errors.
+    pub fn wrong_colour(&self) -> egui::Color32 {
+        match self {
+            Theme::HighContrast => egui::Color32::from_rgb(255, 140, 140),
+            _ => egui::Color32::from_rgb(220, 20, 60),
+        }
+    }
+}
+
+fn high_contrast() -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+    let outline = egui::Stroke::new(2.0, egui::Color32::WHITE);
+    visuals.override_text_color = Some(egui::Color32::WHITE);
+    visuals.panel_fill = egui::Color32::BLACK;
+    visuals.window_fill = egui::Color32::BLACK;
+    visuals.extreme_bg_color = egui::Color32::BLACK;
+    visuals.faint_bg_color = egui::Color32::from_gray(40);
+    visuals.window_stroke = outline;
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.bg_stroke = outline;
+        widget.fg_stroke = outline;
+    }
+    visuals.widgets.noninteractive.bg_fill = egui::Color32::BLACK;
+    visuals.widgets.inactive.bg_fill = egui::Color32::from_gray(30);
+    visuals.widgets.inactive.weak_bg_fill = egui::Color32::from_gray(30);
+    visuals.widgets.hovered.bg_stroke = egui::Stroke::new(3.0, egui::Color32::YELLOW);
+    visuals.widgets.active.bg_stroke = egui::Stroke::new(3.0, egui::Color32::YELLOW);
+    visuals.selection.bg_fill = egui::Color32::from_rgb(0, 90, 200);
+    visuals.selection.stroke = egui::Stroke::new(3.0, egui::Color32::YELLOW);
+    visuals
+}
+
+/// The ways of practising that can be switched off, e.g. by a parent who
+/// wants answers typed rather than picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeModes {
+    pub multiple_choice: bool,
+    pub sprints: bool,
+    pub focus: bool,
+    pub worksheets: bool,
+}
+
+impl Default for PracticeModes {
+    fn default() -> Self {
+        Self {
+            multiple_choice: true,
+            sprints: true,
+            focus: true,
+            worksheets: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub answer_mode: AnswerMode,
+    /// Draw the current problem as a dot array. Corrections always show it.
+    pub show_visual: bool,
+    /// Show the question in words under the numbers.
+    pub show_words: bool,
+    pub locale: Locale,
+    pub theme: Theme,
+    /// Scales text and everything else with it; see `TEXT_SCALES`.
+    pub text_scale: f32,
+    pub modes: PracticeModes,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            answer_mode: AnswerMode::default(),
+            show_visual: false,
+            show_words: false,
+            locale: Locale::default(),
+            theme: Theme::default(),
+            text_scale: 1.0,
+            modes: PracticeModes::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Applies the theme and text size to the running app, growing the window
+    /// to fit larger text.
+    pub fn apply(&self, ctx: &egui::Context) {
+        // Ctrl+Plus and Ctrl+Minus change `text_scale` instead, so the size is kept.
+        ctx.options_mut(|o| o.zoom_with_keyboard = false);
+        ctx.set_visuals(self.theme.visuals());
+        let scale = self
+            .text_scale
+            .clamp(*TEXT_SCALES.start(), *TEXT_SCALES.end());
+        let zoom = ctx.zoom_factor();
+        if zoom != scale {
+            ctx.set_zoom_factor(scale);
+            // Window sizes are in points at the old zoom until the next frame.
+            let grow = scale / zoom;
+            ctx.send_viewport_cmd(egui::ViewportCommand::MinInnerSize(MIN_WINDOW_SIZE * grow));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(WINDOW_SIZE * grow));
+        }
+    }
+
+    /// Handles Ctrl+Plus, Ctrl+Minus and Ctrl+0 as a browser does. Returns
+    /// whether the text size changed.
+    pub fn zoom_with_keyboard(&mut self, ctx: &egui::Context) -> bool {
+        use egui::gui_zoom::kb_shortcuts;
+        let before = self.text_scale;
+        let pressed = |shortcut| ctx.input_mut(|i| i.consume_shortcut(&shortcut));
+        if pressed(kb_shortcuts::ZOOM_RESET) {
+            self.text_scale = 1.0;
+        } else if pressed(kb_shortcuts::ZOOM_IN) || pressed(kb_shortcuts::ZOOM_IN_SECONDARY) {
+            self.text_scale += TEXT_SCALE_STEP;
+        } else if pressed(kb_shortcuts::ZOOM_OUT) {
+            self.text_scale -= TEXT_SCALE_STEP;
+        }
+        self.text_scale = self
+            .text_scale
+            .clamp(*TEXT_SCALES.start(), *TEXT_SCALES.end());
+        self.text_scale != before
+    }
+}
+
+/// True while a value is being dragged or typed, so it isn't saved on every
+/// frame.
+fn editing(response: &egui::Response) -> bool {
+    response.dragged() || response.has_focus()
+}
+
+/// True on the frame a value stops being dragged or typed, which is when it
+/// gets saved.
+fn released(response: &egui::Response) -> bool {
+    response.drag_stopped() || response.lost_focus()
+}
+
+/// What the settings screen changed that is ready to be saved.
+#[derive(Default)]
+pub struct Saves {
+    pub settings: bool,
+    pub progress: bool,
+}
+
+#[derive(Default)]
+pub struct SettingsScreen {
+    pub open: bool,
+}
+
+impl SettingsScreen {
+    /// Edits `settings`, and the scheduling choices kept with `progress`.
+    /// Changes apply straight away; those made by dragging or typing are only
+    /// ready to save once let go.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        settings: &mut Settings,
+        progress: &mut SpacedRepetition,
+    ) -> Saves {
+        let settings_before = settings.clone();
+        let mut saves = Saves::default();
+        let text_scale = egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.add_space(10.0);
+            ui.heading("Settings");
+
+            ui.add_space(10.0);
+            ui.strong("Display");
+            ui.horizontal(|ui| {
+                for theme in Theme::ALL {
+                    ui.radio_value(&mut settings.theme, theme, theme.name());
+                }
+            });
+            let text_scale = ui.add(
+                egui::Slider::new(&mut settings.text_scale, TEXT_SCALES)
+                    .step_by(TEXT_SCALE_STEP as f64)
+                    .text("Text size"),
+            );
+            locale_picker(ui, &mut settings.locale);
+
+            ui.add_space(10.0);
+            ui.strong("Scheduling");
+            let mut commutative = progress.is_commutative();
+            if ui
+                .checkbox(&mut commutative, "Count 3×7 and 7×3 as one fact")
+                .changed()
+            {
+                progress.set_commutative(commutative);
+                saves.progress = true;
+            }
+            let before = progress.time_thresholds();
+            let mut thresholds = before;
+            let seconds = TimeThresholds::MIN_FAST_SECS..=TimeThresholds::MAX_SLOW_SECS;
+            let fast = ui.horizontal(|ui| {
+                let label = ui.label("Fast answers take under");
+                ui.add(
+                    egui::DragValue::new(&mut thresholds.fast_secs)
+                        .range(seconds.clone())
+                        .speed(0.1)
+                        .suffix(" s"),
+                )
+                .labelled_by(label.id)
+            });
+            let slow = ui.horizontal(|ui| {
+                let label = ui.label("Slow answers take over");
+                ui.add(
+                    egui::DragValue::new(&mut thresholds.slow_secs)
+                        .range(seconds)
+                        .speed(0.1)
+                        .suffix(" s"),
+                )
+                .labelled_by(label.id)
+            });
+            if thresholds != before {
+                progress.set_time_thresholds(thresholds);
+            }
+            saves.progress |= released(&fast.inner) || released(&slow.inner);
+
+            ui.add_space(10.0);
+            let heading = ui.strong("Daily goal");
+            let goal = progress.daily().goal;
+            let mut target = goal.target();
+            let mut minutes = matches!(goal, DailyGoal::Minutes(_));
+            let target_field = ui.horizontal(|ui| {
+                let field = ui
+                    .add(egui::DragValue::new(&mut target).range(GOAL_TARGETS))
+                    .labelled_by(heading.id);
+                ui.radio_value(&mut minutes, false, "correct answers");
+                ui.radio_value(&mut minutes, true, "minutes");
+                field
+            });
+            let new_goal = if minutes {
+                DailyGoal::Minutes(target)
+            } else {
+                DailyGoal::CorrectAnswers(target)
+            };
+            if new_goal != goal {
+                progress.set_daily_goal(new_goal);
+            }
+            // The radio buttons save straight away, the target once let go.
+            let target_field = target_field.inner;
+            saves.progress |=
+                (new_goal != goal && !editing(&target_field)) || released(&target_field);
+            let mut freeze = progress.daily().freeze_enabled;
+            if ui
+                .checkbox(&mut freeze, "Use streak freezes on missed days")
+                .changed()
+            {
+                progress.set_streak_freeze(freeze);
+                saves.progress = true;
+            }
+
+            ui.add_space(10.0);
+            ui.strong("Practice");
+            let modes = &mut settings.modes;
+            ui.checkbox(&mut modes.multiple_choice, "Multiple choice");
+            ui.checkbox(&mut modes.sprints, "Sprints");
+            ui.checkbox(&mut modes.focus, "Focus practice");
+            ui.checkbox(&mut modes.worksheets, "Worksheets");
+
+            ui.add_space(10.0);
+            if ui.button("Back to practice").clicked() {
+                self.open = false;
+            }
+            text_scale
+        });
+
+        // The text size follows the slider as it moves, and is saved once
+        // it's let go.
+        let text_scale = text_scale.inner;
+        saves.settings =
+            (*settings != settings_before && !text_scale.dragged()) || released(&text_scale);
+        saves
+    }
+}
+
+/// Radio buttons rather than drop-downs, which are awkward to use from the
+/// keyboard.
+fn locale_picker(ui: &mut egui::Ui, locale: &mut Locale) {
+    let mut language = locale.language;
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Language:");
+        // egui lays text out left to right and its bundled fonts have no
+        // Arabic, so right-to-left languages are web only.
+        for option in Language::ALL.into_iter().filter(|l| !l.is_rtl()) {
+            ui.radio_value(&mut language, option, option.name());
+        }
+    });
+    if language != locale.language {
+        *locale = Locale::new(language);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Sign:");
+        for option in Operator::ALL {
+            ui.radio_value(&mut locale.operator, option, option.symbol().to_string());
+        }
+    });
+}
@@ -0,0 +1,206 @@
+use crate::access;
+use crate::settings::Theme;
+use chrono::Utc;
+use eframe::egui::{self, accesskit::Live};
+use tt_core::focus::Focus;
+use tt_core::locale::Locale;
+use tt_core::problem::Problem;
+use tt_core::spaced_rep::SpacedRepetition;
+use tt_core::sprint::{Sprint, SprintLimit, SprintRecords, SprintResult};
+
+const SPRINT_CHOICES: [SprintLimit; 2] = [SprintLimit::Seconds(60), SprintLimit::Problems(20)];
+const RECENT_SHOWN: usize = 5;
+
+/// What the learner chose once a sprint was over.
+pub enum SprintAction {
+    Again(SprintLimit),
+    Back,
+}
+
+/// A sprint in progress, or the result of the one just finished. The
+/// problems and the answer field are practice's own.
+#[derive(Default)]
+pub struct SprintScreen {
+    sprint: Option<Sprint>,
+    /// Previous sprint answer: whether it was right, the points earned and the problem.
+    feedback: Option<(bool, u32, Problem)>,
+    result: Option<(SprintResult, bool)>,
+}
+
+impl SprintScreen {
+    pub fn start(&mut self, limit: SprintLimit, focus: Option<Focus>) {
+        self.sprint = Some(Sprint::new(limit, focus));
+        self.feedback = None;
+        self.result = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.sprint.is_some()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.sprint.as_ref().is_some_and(|s| s.is_over(Utc::now()))
+    }
+
+    pub fn has_result(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Scores an answer. Returns true once the sprint is over.
+    pub fn record(&mut self, problem: Problem, correct: bool, response_secs: f64) -> bool {
+        let Some(sprint) = &mut self.sprint else {
+            return false;
+        };
+        let points = sprint.record(correct, response_secs);
+        self.feedback = Some((correct, points, problem));
+        sprint.is_over(Utc::now())
+    }
+
+    /// Ends the sprint and keeps its result in `progress`, to show until the
+    /// learner moves on.
+    pub fn finish(&mut self, progress: &mut SpacedRepetition) {
+        if let Some(sprint) = self.sprint.take() {
+            let result = sprint.finish(Utc::now());
+            let new_best = progress.record_sprint(result.clone());
+            self.result = Some((result, new_best));
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.result = None;
+        self.feedback = None;
+    }
+
+    /// The countdown and score, above the problem.
+    pub fn status_ui(&self, ui: &mut egui::Ui) {
+        let Some(sprint) = &self.sprint else {
+            return;
+        };
+        let countdown = match (
+            sprint.remaining_secs(Utc::now()),
+            sprint.remaining_problems(),
+        ) {
+            (Some(secs), _) => format!("{:.0}s left", secs.ceil()),
+            (_, Some(n)) => format!("{} to go", n),
+            _ => String::new(),
+        };
+        ui.add_space(20.0);
+        ui.heading("Sprint");
+        ui.label(egui::RichText::new(countdown).size(28.0).strong());
+        ui.label(format!("Score: {}", sprint.score));
+        ui.add_space(20.0);
+    }
+
+    /// How the last answer went, read out ahead of the next problem.
+    pub fn spoken_feedback(&self, locale: &Locale) -> String {
+        match self.feedback {
+            Some((true, points, _)) => format!("Right, {} points. ", points),
+            Some((false, _, last)) => format!("Wrong: {}. ", locale.equation(&last)),
+            None => String::new(),
+        }
+    }
+
+    /// How the last answer went, under the answer field, and the button to
+    /// stop. Returns true if the learner stopped the sprint.
+    pub fn feedback_ui(&self, ui: &mut egui::Ui, locale: &Locale, theme: Theme) -> bool {
+        match self.feedback {
+            Some((true, points, _)) => {
+                ui.label(
+                    egui::RichText::new(format!("✓ Correct! +{}", points))
+                        .color(theme.right_colour()),
+                );
+            }
+            Some((false, _, last)) => {
+                ui.label(
+                    egui::RichText::new(format!("✗ {}", locale.equation(&last)))
+                        .color(theme.wrong_colour()),
+                );
+            }
+            None => {}
+        }
+
+        ui.add_space(20.0);
+        ui.small_button("Stop sprint").clicked()
+    }
+
+    pub fn result_ui(
+        &self,
+        ui: &mut egui::Ui,
+        records: &SprintRecords,
+        theme: Theme,
+    ) -> Option<SprintAction> {
+        let (result, new_best) = self.result.as_ref()?;
+        let best = records.best(result.limit, result.focus.as_ref());
+
+        ui.vertical_centered(|ui| {
+            ui.add_space(20.0);
+            ui.heading(format!("Sprint over: {}", result.limit.label()));
+            ui.add_space(20.0);
+            let score = ui.label(
+                egui::RichText::new(format!("{} points", result.score))
+                    .size(40.0)
+                    .strong(),
+            );
+            let mut spoken = format!("Sprint over. {} points", result.score);
+            if *new_best {
+                spoken += ", a new personal best";
+            }
+            access::announce(&score, &spoken, Live::Polite);
+            if *new_best {
+                ui.label(
+                    egui::RichText::new("New personal best!")
+                        .size(20.0)
+                        .color(theme.right_colour()),
+                );
+            } else if let Some(best) = best {
+                ui.label(format!("Personal best: {}", best.score));
+            }
+            ui.add_space(10.0);
+            ui.label(format!(
+                "{} correct, {} wrong ({:.0}% accuracy)",
+                result.correct,
+                result.wrong,
+                result.accuracy() * 100.0
+            ));
+            ui.label(format!("Average time: {:.1}s", result.avg_response_secs));
+        });
+
+        ui.add_space(15.0);
+        ui.label("Recent sprints:");
+        for past in records.history().iter().rev().take(RECENT_SHOWN) {
+            ui.label(format!(
+                "{}  {}  {} points, {:.0}%",
+                past.finished_at
+                    .with_timezone(&chrono::Local)
+                    .format("%d %b"),
+                past.limit.label(),
+                past.score,
+                past.accuracy() * 100.0
+            ));
+        }
+
+        let mut action = None;
+        ui.add_space(15.0);
+        ui.horizontal(|ui| {
+            if ui.button("Sprint again").clicked() {
+                action = Some(SprintAction::Again(result.limit));
+            }
+            if ui.button("Back to practice").clicked() {
+                action = Some(SprintAction::Back);
+            }
+        });
+        action
+    }
+}
+
+/// The sprints on offer, under practice. Returns the one picked.
+pub fn start_buttons(ui: &mut egui::Ui) -> Option<SprintLimit> {
+    ui.label("Sprint:");
+    let mut picked = None;
+    for limit in SPRINT_CHOICES {
+        if ui.small_button(limit.label()).clicked() {
+            picked = Some(limit);
+        }
+    }
+    picked
+}
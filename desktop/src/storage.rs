@@ -1,45 +1,443 @@
+use crate::profile::{self, Profile, DEFAULT_NAME};
+use crate::settings::Settings;
+use crate::sync::Account;
+use tt_core::migrate::LoadError;
 use tt_core::spaced_rep::SpacedRepetition;
 use directories::ProjectDirs;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 const APP_NAME: &str = "times_tables";
 const ORG_NAME: &str = "practice";
 const SAVE_FILE: &str = "progress.json";
+const WORKSHEET_FILE: &str = "worksheet.html";
+const ACCOUNT_FILE: &str = "account.json";
+const SETTINGS_FILE: &str = "settings.json";
+/// Each profile's files live in `profiles/<id>/`.
+const PROFILES_DIR: &str = "profiles";
+const PROFILE_FILE: &str = "profile.json";
+/// The id of the profile opened last, to offer it first next time.
+const LAST_PROFILE_FILE: &str = "last_profile";
+/// Backups kept of each profile's progress, newest first: `progress.json.1`
+/// to `progress.json.5`.
+const BACKUP_COUNT: u32 = 5;
+/// A save becomes the newest backup once the last backup is this old, so the
+/// backups reach back over several sessions rather than the last few answers.
+const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Progress loaded from disk.
+#[derive(Default)]
+pub struct Loaded {
+    pub progress: SpacedRepetition,
+    /// What happened, when the save file was damaged and a backup was loaded
+    /// in its place.
+    pub recovered: Option<String>,
+}
 
 fn get_data_dir() -> Option<PathBuf> {
     ProjectDirs::from("com", ORG_NAME, APP_NAME).map(|dirs| dirs.data_dir().to_path_buf())
 }
 
-pub fn save(data: &SpacedRepetition) -> Result<(), String> {
-    let data_dir = get_data_dir().ok_or("Could not determine data directory")?;
+fn get_profile_dir(profile: &str) -> Option<PathBuf> {
+    get_data_dir().map(|dir| dir.join(PROFILES_DIR).join(profile))
+}
 
-    fs::create_dir_all(&data_dir)
+/// `path` with `.suffix` added, e.g. `progress.json.1`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn backup_path(file_path: &Path, n: u32) -> PathBuf {
+    with_suffix(file_path, &n.to_string())
+}
+
+/// Writes a temporary file and renames it over `path`, so a crash part way
+/// leaves either the old file or the new one, never a truncated mix.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp_path = with_suffix(path, "tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path)?;
+
+    // Make the rename itself durable where directories can be synced.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let _ = fs::File::open(dir).and_then(|d| d.sync_all());
+    }
+    Ok(())
+}
+
+/// Makes `json` the newest backup if the last one is old enough, shifting the
+/// others along and dropping the oldest.
+fn back_up(file_path: &Path, json: &str) -> std::io::Result<()> {
+    let newest = backup_path(file_path, 1);
+    let age = fs::metadata(&newest)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if age.is_some_and(|age| age < BACKUP_INTERVAL) {
+        return Ok(());
+    }
+
+    for n in (1..BACKUP_COUNT).rev() {
+        let from = backup_path(file_path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(file_path, n + 1))?;
+        }
+    }
+    write_atomic(&newest, json.as_bytes())
+}
+
+pub fn save(profile: &str, data: &SpacedRepetition) -> Result<(), String> {
+    let profile_dir = get_profile_dir(profile).ok_or("Could not determine data directory")?;
+
+    fs::create_dir_all(&profile_dir)
         .map_err(|e| format!("Failed to create data directory: {}", e))?;
 
-    let file_path = data_dir.join(SAVE_FILE);
+    save_to(&profile_dir.join(SAVE_FILE), data)
+}
+
+fn save_to(file_path: &Path, data: &SpacedRepetition) -> Result<(), String> {
     let json =
         serde_json::to_string_pretty(data).map_err(|e| format!("Failed to serialize: {}", e))?;
 
-    fs::write(&file_path, json).map_err(|e| format!("Failed to write file: {}", e))?;
+    write_atomic(file_path, json.as_bytes()).map_err(|e| format!("Failed to write file: {}", e))?;
+    back_up(file_path, &json).map_err(|e| format!("Failed to back up progress: {}", e))?;
 
     Ok(())
 }
 
-pub fn load() -> Result<SpacedRepetition, String> {
+/// Writes a worksheet page next to the profiles and returns its path. Each
+/// new worksheet replaces the last one.
+pub fn save_worksheet(html: &str) -> Result<PathBuf, String> {
     let data_dir = get_data_dir().ok_or("Could not determine data directory")?;
-    let file_path = data_dir.join(SAVE_FILE);
 
+    fs::create_dir_all(&data_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let file_path = data_dir.join(WORKSHEET_FILE);
+    write_atomic(&file_path, html.as_bytes())
+        .map_err(|e| format!("Failed to write worksheet: {}", e))?;
+
+    Ok(file_path)
+}
+
+/// Saves the profile's signed-in server account, or removes it when signing
+/// out.
+pub fn save_account(profile: &str, account: Option<&Account>) -> Result<(), String> {
+    let profile_dir = get_profile_dir(profile).ok_or("Could not determine data directory")?;
+    let file_path = profile_dir.join(ACCOUNT_FILE);
+
+    let Some(account) = account else {
+        return match fs::remove_file(&file_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove account: {}", e))
+            }
+            _ => Ok(()),
+        };
+    };
+
+    fs::create_dir_all(&profile_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let json = serde_json::to_string_pretty(account)
+        .map_err(|e| format!("Failed to serialize: {}", e))?;
+    write_atomic(&file_path, json.as_bytes())
+        .map_err(|e| format!("Failed to write account: {}", e))
+}
+
+/// The profile's signed-in account, if there is one. An unreadable file
+/// counts as signed out.
+pub fn load_account(profile: &str) -> Option<Account> {
+    let file_path = get_profile_dir(profile)?.join(ACCOUNT_FILE);
+    let content = fs::read_to_string(file_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn save_settings(profile: &str, settings: &Settings) -> Result<(), String> {
+    let profile_dir = get_profile_dir(profile).ok_or("Could not determine data directory")?;
+
+    fs::create_dir_all(&profile_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize: {}", e))?;
+    write_atomic(&profile_dir.join(SETTINGS_FILE), json.as_bytes())
+        .map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// The profile's settings. Missing or unreadable settings are the defaults,
+/// as they are quick to choose again.
+pub fn load_settings(profile: &str) -> Settings {
+    get_profile_dir(profile)
+        .and_then(|dir| fs::read_to_string(dir.join(SETTINGS_FILE)).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Loads the profile's saved progress, or `Ok(None)` if nothing has been
+/// saved yet. A damaged save file is set aside and the newest backup that
+/// loads takes its place.
+pub fn load(profile: &str) -> Result<Option<Loaded>, String> {
+    let profile_dir = get_profile_dir(profile).ok_or("Could not determine data directory")?;
+    load_from(&profile_dir.join(SAVE_FILE))
+}
+
+fn load_from(file_path: &Path) -> Result<Option<Loaded>, String> {
     if !file_path.exists() {
-        return Err("No save file found".to_string());
+        return Ok(None);
+    }
+
+    // A file that can't be read, e.g. because it isn't valid UTF-8 any more,
+    // is as damaged as one that doesn't parse.
+    let error = match fs::read_to_string(file_path) {
+        Err(e) => format!("Failed to read file: {}", e),
+        Ok(content) => match SpacedRepetition::from_json(&content) {
+            Ok(progress) => {
+                return Ok(Some(Loaded {
+                    progress,
+                    recovered: None,
+                }))
+            }
+            // Not damaged, just newer than this app, and so are its backups.
+            Err(e @ LoadError::TooNew { .. }) => {
+                return Err(format!("Failed to load {}: {}", file_path.display(), e))
+            }
+            Err(e) => e.to_string(),
+        },
+    };
+
+    recover(file_path).map(Some).map_err(|e| {
+        format!("Failed to load {}: {}. {}", file_path.display(), error, e)
+    })
+}
+
+/// Puts the newest backup that loads in place of the damaged file at
+/// `file_path`, keeping the damaged file as `progress.json.damaged`.
+fn recover(file_path: &Path) -> Result<Loaded, String> {
+    let (backup, content, progress) = (1..=BACKUP_COUNT)
+        .find_map(|n| {
+            let backup = backup_path(file_path, n);
+            let content = fs::read_to_string(&backup).ok()?;
+            let progress = SpacedRepetition::from_json(&content).ok()?;
+            Some((backup, content, progress))
+        })
+        .ok_or("No backup could be loaded either")?;
+
+    let saved_at = fs::metadata(&backup)
+        .and_then(|m| m.modified())
+        .map(|modified| {
+            chrono::DateTime::<chrono::Local>::from(modified)
+                .format("%d %b %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| "earlier".to_string());
+
+    let damaged = with_suffix(file_path, "damaged");
+    fs::rename(file_path, &damaged)
+        .map_err(|e| format!("Failed to set the damaged file aside: {}", e))?;
+    write_atomic(file_path, content.as_bytes())
+        .map_err(|e| format!("Failed to restore the backup: {}", e))?;
+
+    Ok(Loaded {
+        progress,
+        recovered: Some(format!(
+            "Your saved progress was damaged, so the backup from {} was loaded instead. \
+             Practice since then is missing. The damaged file was kept as {}.",
+            saved_at,
+            damaged.display()
+        )),
+    })
+}
+
+/// Starts fresh only when there is no save file. A file that exists but
+/// can't be loaded, from itself or a backup, is reported as an error, so the
+/// caller doesn't overwrite it.
+pub fn load_or_new(profile: &str) -> Result<Loaded, String> {
+    Ok(load(profile)?.unwrap_or_default())
+}
+
+pub fn save_profile(profile: &Profile) -> Result<(), String> {
+    let profile_dir = get_profile_dir(&profile.id).ok_or("Could not determine data directory")?;
+
+    fs::create_dir_all(&profile_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    let json = serde_json::to_string_pretty(profile)
+        .map_err(|e| format!("Failed to serialize: {}", e))?;
+    write_atomic(&profile_dir.join(PROFILE_FILE), json.as_bytes())
+        .map_err(|e| format!("Failed to write profile: {}", e))
+}
+
+/// All profiles, sorted by name. When there are none yet, a first profile is
+/// made and any progress saved before profiles existed is moved into it.
+pub fn load_profiles() -> Result<Vec<Profile>, String> {
+    let data_dir = get_data_dir().ok_or("Could not determine data directory")?;
+    let profiles_dir = data_dir.join(PROFILES_DIR);
+
+    let mut profiles = Vec::new();
+    match fs::read_dir(&profiles_dir) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    let id = entry.file_name().to_string_lossy().into_owned();
+                    profiles.push(read_profile(&entry.path(), id));
+                }
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to read profiles: {}", e)),
+    }
+
+    if profiles.is_empty() {
+        profiles.push(create_first_profile(&data_dir)?);
+    }
+    profiles.sort_by_key(|p| p.name.to_lowercase());
+    Ok(profiles)
+}
+
+/// A profile folder whose details can't be read is still listed, under the
+/// folder's name, so its progress can be reached.
+fn read_profile(profile_dir: &Path, id: String) -> Profile {
+    let stored = fs::read_to_string(profile_dir.join(PROFILE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Profile>(&content).ok());
+    match stored {
+        Some(mut profile) => {
+            profile.id = id;
+            profile
+        }
+        None => Profile::new(id.clone(), &id),
+    }
+}
+
+fn create_first_profile(data_dir: &Path) -> Result<Profile, String> {
+    let profile = Profile::new(profile::next_id(&[]), DEFAULT_NAME);
+    let profile_dir = data_dir.join(PROFILES_DIR).join(&profile.id);
+    fs::create_dir_all(&profile_dir)
+        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    for file in [SAVE_FILE, ACCOUNT_FILE] {
+        let old_path = data_dir.join(file);
+        if old_path.exists() {
+            fs::rename(&old_path, profile_dir.join(file)).map_err(|e| {
+                format!("Failed to move {} into a profile: {}", old_path.display(), e)
+            })?;
+        }
     }
 
-    let content =
-        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    save_profile(&profile)?;
+    Ok(profile)
+}
+
+pub fn save_last_profile(profile: &str) -> Result<(), String> {
+    let data_dir = get_data_dir().ok_or("Could not determine data directory")?;
+    write_atomic(&data_dir.join(LAST_PROFILE_FILE), profile.as_bytes())
+        .map_err(|e| format!("Failed to write file: {}", e))
+}
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to deserialize: {}", e))
+pub fn load_last_profile() -> Option<String> {
+    let content = fs::read_to_string(get_data_dir()?.join(LAST_PROFILE_FILE)).ok()?;
+    Some(content.trim().to_string())
 }
 
-pub fn load_or_new() -> SpacedRepetition {
-    load().unwrap_or_else(|_| SpacedRepetition::new())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+    use tt_core::problem::Problem;
+
+    /// An empty directory of its own for each test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tt-storage-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn answered(times: usize) -> SpacedRepetition {
+        let mut sr = SpacedRepetition::new();
+        for _ in 0..times {
+            sr.record_answer(&Problem::new(1, 2), true, 2.0);
+        }
+        sr
+    }
+
+    fn correct_in(path: &Path) -> u32 {
+        let content = fs::read_to_string(path).unwrap();
+        let progress = SpacedRepetition::from_json(&content).unwrap();
+        progress.total_correct()
+    }
+
+    /// Makes the newest backup old enough for the next save to rotate.
+    fn age_newest_backup(file_path: &Path) {
+        let old = SystemTime::now() - BACKUP_INTERVAL * 2;
+        let newest = fs::File::options()
+            .write(true)
+            .open(backup_path(file_path, 1))
+            .unwrap();
+        newest.set_modified(old).unwrap();
+    }
+
+    #[test]
+    fn a_damaged_save_file_is_recovered_from_the_newest_good_backup() {
+        let dir = test_dir("recover");
+        let file_path = dir.join(SAVE_FILE);
+        save_to(&file_path, &answered(1)).unwrap();
+        age_newest_backup(&file_path);
+        save_to(&file_path, &answered(2)).unwrap();
+        // The newest backup is damaged too, so the one before it is used.
+        fs::write(backup_path(&file_path, 1), "{\"stats\": [").unwrap();
+        fs::write(&file_path, "{\"version\": 5, \"sta").unwrap();
+
+        let loaded = load_from(&file_path).unwrap().unwrap();
+        assert_eq!(loaded.progress.total_correct(), 1);
+        assert!(loaded.recovered.is_some());
+        assert!(with_suffix(&file_path, "damaged").exists());
+        // The backup took the damaged file's place.
+        let reloaded = load_from(&file_path).unwrap().unwrap();
+        assert!(reloaded.recovered.is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn saving_keeps_a_limited_number_of_backups() {
+        let dir = test_dir("rotate");
+        let file_path = dir.join(SAVE_FILE);
+        for times in 0..BACKUP_COUNT as usize + 3 {
+            save_to(&file_path, &answered(times)).unwrap();
+            age_newest_backup(&file_path);
+        }
+        let newest_first: Vec<u32> = (1..=BACKUP_COUNT)
+            .map(|n| correct_in(&backup_path(&file_path, n)))
+            .collect();
+        assert_eq!(newest_first, [7, 6, 5, 4, 3]);
+        assert!(!backup_path(&file_path, BACKUP_COUNT + 1).exists());
+
+        // Saves close together don't push older backups out.
+        save_to(&file_path, &answered(8)).unwrap();
+        save_to(&file_path, &answered(9)).unwrap();
+        assert_eq!(correct_in(&backup_path(&file_path, 1)), 8);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_failed_save_leaves_the_old_file_intact() {
+        let dir = test_dir("failed-write");
+        let file_path = dir.join(SAVE_FILE);
+        save_to(&file_path, &answered(3)).unwrap();
+        let before = fs::read_to_string(&file_path).unwrap();
+        // Nothing can be written where the temporary file should go.
+        fs::create_dir(with_suffix(&file_path, "tmp")).unwrap();
+
+        assert!(save_to(&file_path, &answered(4)).is_err());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), before);
+        let loaded = load_from(&file_path).unwrap().unwrap();
+        assert_eq!(loaded.progress.total_correct(), 3);
+        let _ = fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,319 @@
+//! Signing in to a times tables server and keeping progress in step with it.
+//!
+//! Requests block, so the app runs them on a background thread with `spawn`
+//! and picks up the `Event` when it arrives. Progress is combined with
+//! `SpacedRepetition::merge` on both ends, so practice done offline on either
+//! side is kept whenever the two next meet.
+
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tt_core::spaced_rep::SpacedRepetition;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// How often new answers are sent to the server, and retried when offline.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// How often to check for practice done elsewhere when there's nothing new here.
+const PULL_INTERVAL: Duration = Duration::from_secs(600);
+
+/// A signed-in server account, saved between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    /// Base URL, e.g. "https://tables.example.org".
+    pub server: String,
+    pub username: String,
+    pub token: String,
+}
+
+pub enum Event {
+    SignedIn(Result<Account, String>),
+    /// The server's progress after merging ours into it.
+    Synced(Result<Box<SpacedRepetition>, SyncError>),
+}
+
+pub enum SyncError {
+    /// The server no longer accepts the token; sign in again.
+    SignedOut,
+    /// Couldn't reach the server, or it failed. Worth trying again later.
+    Failed(String),
+}
+
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    username: &'a str,
+    password: &'a str,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    token: String,
+}
+
+/// Runs `job` on a background thread and hands back where its event will
+/// arrive.
+pub fn spawn(job: impl FnOnce() -> Event + Send + 'static) -> Receiver<Event> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(job());
+    });
+    receiver
+}
+
+fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| format!("Could not start a connection: {}", e))
+}
+
+fn url(server: &str, path: &str) -> String {
+    format!("{}{}", server.trim().trim_end_matches('/'), path)
+}
+
+/// Signs in with the server's own `/api/login`.
+pub fn sign_in(server: &str, username: &str, password: &str) -> Result<Account, String> {
+    let response = client()?
+        .post(url(server, "/api/login"))
+        .json(&LoginRequest { username, password })
+        .send()
+        .map_err(|e| format!("Could not reach {}: {}", server, e))?;
+    if !response.status().is_success() {
+        let message = response.text().unwrap_or_default();
+        return Err(if message.is_empty() {
+            "Sign-in failed".to_string()
+        } else {
+            message
+        });
+    }
+    let body: TokenResponse = response
+        .json()
+        .map_err(|e| format!("Unexpected reply from the server: {}", e))?;
+    Ok(Account {
+        server: server.trim().trim_end_matches('/').to_string(),
+        username: username.trim().to_string(),
+        token: body.token,
+    })
+}
+
+/// Sends our progress to be merged into the account's and returns the merged
+/// result.
+pub fn sync(
+    account: &Account,
+    progress: &SpacedRepetition,
+) -> Result<Box<SpacedRepetition>, SyncError> {
+    let body = serde_json::to_string(progress)
+        .map_err(|e| SyncError::Failed(format!("Failed to serialize: {}", e)))?;
+    let response = client()
+        .map_err(SyncError::Failed)?
+        .post(url(&account.server, "/api/sync"))
+        .bearer_auth(&account.token)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .map_err(|e| SyncError::Failed(format!("Offline: {}", e)))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(SyncError::SignedOut);
+    }
+    let text = response
+        .text()
+        .map_err(|e| SyncError::Failed(format!("Lost the connection: {}", e)))?;
+    if !status.is_success() {
+        return Err(SyncError::Failed(format!(
+            "Server said {}: {}",
+            status, text
+        )));
+    }
+    SpacedRepetition::from_json(&text)
+        .map(Box::new)
+        .map_err(|e| SyncError::Failed(format!("Server sent progress we can't load: {}", e)))
+}
+
+/// Signing in and syncing, shown under practice, with the job running in the
+/// background. `progress` is `None` wherever nothing should be sent.
+pub struct SyncScreen {
+    pub open: bool,
+    /// The server account progress is shared with, if signed in.
+    pub account: Option<Account>,
+    server: String,
+    username: String,
+    password: String,
+    /// The sign-in or sync running in the background.
+    job: Option<Receiver<Event>>,
+    last_sync: Option<Instant>,
+    /// The newest answer the server has, to tell whether there's more to send.
+    synced_answer: Option<String>,
+    status: Option<String>,
+}
+
+impl SyncScreen {
+    pub fn new(account: Option<Account>) -> Self {
+        Self {
+            open: false,
+            account,
+            server: String::new(),
+            username: String::new(),
+            password: String::new(),
+            job: None,
+            last_sync: None,
+            synced_answer: None,
+            status: None,
+        }
+    }
+
+    fn sign_in(&mut self) {
+        let (server, username, password) = (
+            self.server.clone(),
+            self.username.clone(),
+            std::mem::take(&mut self.password),
+        );
+        self.status = Some("Signing in…".to_string());
+        self.job = Some(spawn(move || {
+            Event::SignedIn(sign_in(&server, &username, &password))
+        }));
+    }
+
+    fn sign_out(&mut self, profile_id: &str) {
+        self.account = None;
+        self.synced_answer = None;
+        self.status = storage::save_account(profile_id, None).err();
+    }
+
+    /// Sends progress to the server in the background.
+    fn start(&mut self, progress: Option<&SpacedRepetition>) {
+        let (Some(account), Some(progress)) = (self.account.clone(), progress) else {
+            return;
+        };
+        if self.job.is_some() {
+            return;
+        }
+        let progress = progress.clone();
+        self.last_sync = Some(Instant::now());
+        self.status = Some("Syncing…".to_string());
+        self.job = Some(spawn(move || Event::Synced(sync(&account, &progress))));
+    }
+
+    /// Syncs on start, then whenever there are new answers, and now and then
+    /// to pick up practice done elsewhere.
+    pub fn auto_sync(&mut self, progress: Option<&SpacedRepetition>) {
+        let newest = progress.and_then(|p| p.journal().last()).map(|e| &e.id);
+        let unsynced = newest != self.synced_answer.as_ref();
+        let due = match self.last_sync {
+            None => true,
+            Some(at) if unsynced => at.elapsed() >= SYNC_INTERVAL,
+            Some(at) => at.elapsed() >= PULL_INTERVAL,
+        };
+        if due {
+            self.start(progress);
+        }
+    }
+
+    /// Picks up what the background job came back with. Returns the server's
+    /// progress after a sync, for the caller to merge in.
+    pub fn poll(&mut self, ctx: &egui::Context, profile_id: &str) -> Option<Box<SpacedRepetition>> {
+        let event = match self.job.as_ref()?.try_recv() {
+            Ok(event) => event,
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(200));
+                return None;
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.job = None;
+                return None;
+            }
+        };
+        self.job = None;
+
+        match event {
+            Event::SignedIn(Ok(account)) => {
+                let saved = storage::save_account(profile_id, Some(&account));
+                self.status = Some(match saved {
+                    Ok(()) => format!("Signed in as {}", account.username),
+                    Err(e) => e,
+                });
+                self.account = Some(account);
+                // Sync straight away.
+                self.last_sync = None;
+            }
+            Event::SignedIn(Err(e)) => self.status = Some(e),
+            Event::Synced(Ok(merged)) => {
+                self.synced_answer = merged.journal().last().map(|e| e.id.clone());
+                let now = chrono::Local::now();
+                self.status = Some(format!("Synced at {}", now.format("%H:%M")));
+                return Some(merged);
+            }
+            Event::Synced(Err(SyncError::SignedOut)) => {
+                self.sign_out(profile_id);
+                self.status = Some("The server signed you out. Please sign in again".to_string());
+            }
+            Event::Synced(Err(SyncError::Failed(e))) => {
+                self.status = Some(format!("{}. Will try again shortly", e));
+            }
+        }
+        None
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, profile_id: &str, progress: Option<&SpacedRepetition>) {
+        let busy = self.job.is_some();
+        if let Some(account) = &self.account {
+            ui.label(format!(
+                "Signed in as {} on {}",
+                account.username, account.server
+            ));
+        } else {
+            ui.label("Sign in to share progress with the website");
+            egui::Grid::new("sync_sign_in")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Server:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.server)
+                            .hint_text("https://…")
+                            .desired_width(200.0),
+                    );
+                    ui.end_row();
+                    ui.label("Username:");
+                    ui.add(egui::TextEdit::singleline(&mut self.username).desired_width(200.0));
+                    ui.end_row();
+                    ui.label("Password:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.password)
+                            .password(true)
+                            .desired_width(200.0),
+                    );
+                    ui.end_row();
+                });
+        }
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+        ui.horizontal(|ui| {
+            if self.account.is_some() {
+                if ui
+                    .add_enabled(!busy, egui::Button::new("Sync now"))
+                    .clicked()
+                {
+                    self.start(progress);
+                }
+                if ui
+                    .add_enabled(!busy, egui::Button::new("Sign out"))
+                    .clicked()
+                {
+                    self.sign_out(profile_id);
+                }
+            } else if ui
+                .add_enabled(!busy, egui::Button::new("Sign in"))
+                .clicked()
+            {
+                self.sign_in();
+            }
+            if ui.button("Close").clicked() {
+                self.open = false;
+            }
+        });
+    }
+}
@@ -0,0 +1,46 @@
+use crate::access;
+use eframe::egui;
+use tt_core::visual::Visual;
+
+const DOT_SPACING: f32 = 9.0;
+const DOT_RADIUS: f32 = 3.0;
+/// Extra space between the blocks of a split array.
+const PART_GAP: f32 = 8.0;
+const PART_COLOURS: [egui::Color32; 2] = [
+    egui::Color32::from_rgb(37, 99, 235),
+    egui::Color32::from_rgb(22, 163, 74),
+];
+
+/// Draws the fact as a dot array, one colour per block, with its caption.
+/// `answered` shows the caption worked through to the answer.
+pub fn show(ui: &mut egui::Ui, visual: &Visual, answered: bool) {
+    let height =
+        visual.rows as f32 * DOT_SPACING + visual.parts.len().saturating_sub(1) as f32 * PART_GAP;
+    let width = visual.cols as f32 * DOT_SPACING;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), egui::Sense::hover());
+    access::describe(
+        &response,
+        &format!("Dot array, {} rows of {} dots", visual.rows, visual.cols),
+    );
+    let painter = ui.painter_at(rect);
+
+    let mut y = rect.top() + DOT_SPACING / 2.0;
+    for (i, part) in visual.parts.iter().enumerate() {
+        let colour = PART_COLOURS[i % PART_COLOURS.len()];
+        for _ in 0..part.rows {
+            for col in 0..part.cols {
+                let x = rect.left() + DOT_SPACING / 2.0 + col as f32 * DOT_SPACING;
+                painter.circle_filled(egui::pos2(x, y), DOT_RADIUS, colour);
+            }
+            y += DOT_SPACING;
+        }
+        y += PART_GAP;
+    }
+
+    let caption = if answered {
+        &visual.worked
+    } else {
+        &visual.caption
+    };
+    ui.label(egui::RichText::new(caption).weak());
+}
@@ -0,0 +1,99 @@
+use crate::storage;
+use chrono::Utc;
+use eframe::egui;
+use std::path::Path;
+use std::process::Command;
+use tt_core::spaced_rep::SpacedRepetition;
+use tt_core::worksheet::{WorksheetOptions, MAX_COLUMNS, MAX_PROBLEMS};
+
+/// Opens a file with the system's default application, e.g. a worksheet in
+/// the browser so it can be printed from there.
+fn open_with_system(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(path).spawn().map(|_| ())
+}
+
+/// Makes a worksheet to print, shown under practice.
+#[derive(Default)]
+pub struct WorksheetPicker {
+    pub open: bool,
+    options: WorksheetOptions,
+    /// Seed to print an earlier sheet again; blank for a new one.
+    seed: String,
+    /// Where the last worksheet went, or why it couldn't be made.
+    status: Option<String>,
+}
+
+impl WorksheetPicker {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.status = None;
+    }
+
+    fn print(&mut self, progress: &SpacedRepetition) {
+        let seed = match self.seed.trim() {
+            "" => Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64,
+            text => match text.parse() {
+                Ok(seed) => seed,
+                Err(_) => {
+                    self.status = Some("The sheet number should be a whole number".to_string());
+                    return;
+                }
+            },
+        };
+        let options = WorksheetOptions {
+            seed,
+            ..self.options.clone()
+        };
+        let worksheet = progress.worksheet(options);
+        let html = worksheet.to_html("Times tables worksheet");
+        self.status = Some(match storage::save_worksheet(&html) {
+            Ok(path) => match open_with_system(&path) {
+                Ok(()) => format!("Sheet {} opened for printing", worksheet.options.seed),
+                Err(_) => format!("Saved to {}", path.display()),
+            },
+            Err(e) => e,
+        });
+    }
+
+    /// Picks the sheet's facts from `progress`.
+    pub fn ui(&mut self, ui: &mut egui::Ui, progress: &SpacedRepetition) {
+        let options = &mut self.options;
+        ui.horizontal(|ui| {
+            ui.label("Problems:");
+            ui.add(egui::DragValue::new(&mut options.count).range(1..=MAX_PROBLEMS));
+            ui.label("Columns:");
+            ui.add(egui::DragValue::new(&mut options.columns).range(1..=MAX_COLUMNS));
+            ui.checkbox(&mut options.answer_key, "Answer key");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Sheet number:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.seed)
+                    .hint_text("new sheet")
+                    .desired_width(160.0),
+            );
+        });
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Print").clicked() {
+                self.print(progress);
+            }
+            if ui.button("Close").clicked() {
+                self.open = false;
+            }
+        });
+    }
+}
@@ -14,7 +14,23 @@ use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqliteConnectOptions, Row, SqlitePool};
 use std::sync::Arc;
-use tt_core::{problem::Problem, spaced_rep::SpacedRepetition};
+use tt_core::{
+    choice::{AnswerMode, MultipleChoice},
+    daily::{local_utc_offset_mins, DailyGoal, DailySummary},
+    focus::Focus,
+    hint::Hint,
+    journal::{AppliedAnswer, ClientAnswer},
+    locale::{Language, Locale, Operator},
+    migrate::LoadError,
+    parse::parse_answer,
+    problem::Problem,
+    session::Session,
+    slip::Mistake,
+    spaced_rep::SpacedRepetition,
+    sprint::{Sprint, SprintLimit, SprintResult},
+    visual::Visual,
+    worksheet::{WorksheetOptions, MAX_COLUMNS, MAX_PROBLEMS},
+};
 
 // ── App state ─────────────────────────────────────────────────────────────────
 
@@ -40,10 +56,44 @@ struct TokenResponse {
     token: String,
 }
 
+/// Number of options offered in multiple-choice mode.
+const CHOICE_COUNT: usize = 4;
+
 #[derive(Serialize)]
 struct ProblemDto {
     a: u8,
     b: u8,
+    /// The question written for the client's locale, e.g. "7 · 8 = ?".
+    text: String,
+    /// The question in words, e.g. "What is seven times eight?".
+    words: String,
+    /// Show `text` and `words` right to left.
+    rtl: bool,
+    /// Options to pick from, present only in multiple-choice mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    choices: Option<Vec<u32>>,
+    visual: Visual,
+}
+
+/// How the client presents problems, passed in the query string to the
+/// endpoints that hand out a new problem: `?mode=multiple_choice&lang=fr`.
+/// Answers carry their own mode in the body.
+#[derive(Deserialize, Default)]
+struct ViewQuery {
+    #[serde(default)]
+    mode: AnswerMode,
+    lang: Option<Language>,
+    operator: Option<Operator>,
+}
+
+impl ViewQuery {
+    fn locale(&self) -> Locale {
+        let locale = Locale::new(self.lang.unwrap_or_default());
+        match self.operator {
+            Some(operator) => locale.with_operator(operator),
+            None => locale,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -53,15 +103,134 @@ struct StateResponse {
     total: usize,
     due: usize,
     grid: Vec<&'static str>,
+    focus: Option<FocusDto>,
+    session: Option<SessionDto>,
+    daily: DailySummary,
+}
+
+#[derive(Serialize)]
+struct SessionDto {
+    id: String,
+    started_at: chrono::DateTime<Utc>,
+    streak: u32,
+    best_streak: u32,
+    correct: u32,
+    wrong: u32,
+}
+
+impl From<&Session> for SessionDto {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.clone(),
+            started_at: session.started_at,
+            streak: session.streak,
+            best_streak: session.best_streak,
+            correct: session.correct,
+            wrong: session.wrong,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FocusRequest {
+    #[serde(default)]
+    tables: Vec<u8>,
+    #[serde(default)]
+    facts: Vec<Problem>,
+}
+
+#[derive(Serialize)]
+struct FocusDto {
+    tables: Vec<u8>,
+    facts: Vec<Problem>,
+    label: String,
+}
+
+impl From<Focus> for FocusDto {
+    fn from(focus: Focus) -> Self {
+        let label = focus.label();
+        Self {
+            tables: focus.tables,
+            facts: focus.facts,
+            label,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct AnswerRequest {
     a: u8,
     b: u8,
-    answer: u32,
+    answer: AnswerInput,
     #[serde(default = "default_elapsed")]
     elapsed_secs: f64,
+    /// How the answer was given; the next problem is presented the same way.
+    #[serde(default)]
+    mode: AnswerMode,
+}
+
+/// An answer as a number, or as the text the learner typed to be read here.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnswerInput {
+    Number(u32),
+    Text(String),
+}
+
+impl AnswerRequest {
+    /// The fact answered; factors outside 1..=12 are a 400, as in a batch.
+    fn problem(&self) -> Result<Problem, (StatusCode, String)> {
+        let problem = Problem::new(self.a, self.b);
+        match problem.index() {
+            Some(_) => Ok(problem),
+            None => Err(app_err(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown problem {}", problem.key()),
+            )),
+        }
+    }
+}
+
+impl AnswerInput {
+    /// Text is read in the learner's language; anything unreadable is a 400
+    /// saying so, and nothing is recorded.
+    fn value(&self, view: &ViewQuery) -> Result<u32, (StatusCode, String)> {
+        match self {
+            AnswerInput::Number(n) => Ok(*n),
+            AnswerInput::Text(text) => parse_answer(text, Some(view.lang.unwrap_or_default()))
+                .map_err(|e| app_err(StatusCode::BAD_REQUEST, e)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ParseRequest {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct ParseResponse {
+    value: u32,
+}
+
+/// Most answers accepted in one batch.
+const MAX_BATCH_ANSWERS: usize = 500;
+
+#[derive(Deserialize)]
+struct BatchAnswerRequest {
+    answers: Vec<ClientAnswer>,
+}
+
+#[derive(Serialize)]
+struct BatchAnswerResponse {
+    /// One per answer sent, in the same order.
+    results: Vec<AppliedAnswer>,
+    mastered: usize,
+    total: usize,
+    due: usize,
+    grid: Vec<&'static str>,
+    session: SessionDto,
+    daily: DailySummary,
 }
 
 fn default_elapsed() -> f64 {
@@ -72,11 +241,124 @@ fn default_elapsed() -> f64 {
 struct AnswerResponse {
     correct: bool,
     correct_answer: u32,
+    /// The answer as it was read, which may have been typed in words.
+    user_answer: u32,
+    /// Whether a wrong answer looks like a slip or a fact not yet known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mistake: Option<MistakeDto>,
+    /// A strategy for the fact, given with wrong answers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<Hint>,
+    /// The answered fact drawn as an array, given with wrong answers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visual: Option<Visual>,
     next_problem: ProblemDto,
     mastered: usize,
     total: usize,
     due: usize,
     grid: Vec<&'static str>,
+    focus: Option<FocusDto>,
+    session: SessionDto,
+    daily: DailySummary,
+}
+
+#[derive(Serialize)]
+struct MistakeDto {
+    kind: Mistake,
+    slip: bool,
+    text: &'static str,
+}
+
+impl From<Mistake> for MistakeDto {
+    fn from(mistake: Mistake) -> Self {
+        Self {
+            kind: mistake,
+            slip: mistake.is_slip(),
+            text: mistake.describe(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SettingsRequest {
+    commutative: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct SettingsResponse {
+    commutative: bool,
+}
+
+#[derive(Deserialize)]
+struct GoalRequest {
+    goal: DailyGoal,
+    streak_freeze: Option<bool>,
+}
+
+/// The learner's clock, passed as `?utc_offset=` in minutes east of UTC so
+/// answers, the goal and the streak follow their day rather than the
+/// server's.
+#[derive(Deserialize)]
+struct ClockQuery {
+    utc_offset: Option<i32>,
+}
+
+impl ClockQuery {
+    fn utc_offset_mins(&self) -> i32 {
+        self.utc_offset.unwrap_or_else(local_utc_offset_mins)
+    }
+}
+
+#[derive(Deserialize)]
+struct SprintStartRequest {
+    limit: SprintLimit,
+}
+
+#[derive(Serialize)]
+struct SprintStateResponse {
+    problem: ProblemDto,
+    limit: SprintLimit,
+    remaining_secs: Option<f64>,
+    remaining_problems: Option<u32>,
+    score: u32,
+    correct: u32,
+    wrong: u32,
+    best_score: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct SprintAnswerResponse {
+    correct: bool,
+    correct_answer: u32,
+    points: u32,
+    score: u32,
+    next_problem: Option<ProblemDto>,
+    remaining_secs: Option<f64>,
+    remaining_problems: Option<u32>,
+    finished: Option<SprintFinishedResponse>,
+    session: SessionDto,
+}
+
+#[derive(Serialize)]
+struct SprintFinishedResponse {
+    result: SprintResult,
+    new_best: bool,
+}
+
+#[derive(Serialize)]
+struct SprintHistoryResponse {
+    history: Vec<SprintResult>,
+    bests: Vec<SprintResult>,
+}
+
+/// Worksheet settings from the query string. Anything left out takes the
+/// default, and a missing seed gets a random one.
+#[derive(Deserialize)]
+struct WorksheetQuery {
+    count: Option<usize>,
+    columns: Option<usize>,
+    answer_key: Option<bool>,
+    seed: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -114,6 +396,11 @@ fn internal(msg: impl ToString) -> (StatusCode, String) {
     app_err(StatusCode::INTERNAL_SERVER_ERROR, msg)
 }
 
+/// Neither the focus nor the unlocked tables have a problem to ask.
+fn nothing_to_practise() -> (StatusCode, String) {
+    app_err(StatusCode::CONFLICT, "There is nothing to practise")
+}
+
 // ── Auth helpers ──────────────────────────────────────────────────────────────
 
 async fn authenticate(db: &SqlitePool, headers: &HeaderMap) -> Option<i64> {
@@ -167,7 +454,7 @@ async fn load_user_state(
     match row {
         Some(r) => {
             let data: String = r.try_get("data").map_err(internal)?;
-            serde_json::from_str(&data).map_err(internal)
+            SpacedRepetition::from_json(&data).map_err(internal)
         }
         None => Ok(SpacedRepetition::new()),
     }
@@ -191,17 +478,217 @@ async fn save_user_state(
     Ok(())
 }
 
+async fn load_focus(
+    db: &SqlitePool,
+    user_id: i64,
+) -> Result<Option<Focus>, (StatusCode, String)> {
+    let row = sqlx::query("SELECT data FROM focus WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(internal)?;
+
+    match row {
+        Some(r) => {
+            let data: String = r.try_get("data").map_err(internal)?;
+            serde_json::from_str(&data).map(Some).map_err(internal)
+        }
+        None => Ok(None),
+    }
+}
+
+async fn save_focus(
+    db: &SqlitePool,
+    user_id: i64,
+    focus: Option<&Focus>,
+) -> Result<(), (StatusCode, String)> {
+    match focus {
+        Some(focus) => {
+            let data = serde_json::to_string(focus).map_err(internal)?;
+            sqlx::query(
+                "INSERT INTO focus (user_id, data) VALUES (?, ?)
+                 ON CONFLICT(user_id) DO UPDATE SET data = excluded.data",
+            )
+            .bind(user_id)
+            .bind(&data)
+            .execute(db)
+            .await
+            .map_err(internal)?;
+        }
+        None => {
+            sqlx::query("DELETE FROM focus WHERE user_id = ?")
+                .bind(user_id)
+                .execute(db)
+                .await
+                .map_err(internal)?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_sprint(
+    db: &SqlitePool,
+    user_id: i64,
+) -> Result<Option<Sprint>, (StatusCode, String)> {
+    let row = sqlx::query("SELECT data FROM sprints WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(internal)?;
+
+    match row {
+        Some(r) => {
+            let data: String = r.try_get("data").map_err(internal)?;
+            serde_json::from_str(&data).map(Some).map_err(internal)
+        }
+        None => Ok(None),
+    }
+}
+
+async fn save_sprint(
+    db: &SqlitePool,
+    user_id: i64,
+    sprint: Option<&Sprint>,
+) -> Result<(), (StatusCode, String)> {
+    match sprint {
+        Some(sprint) => {
+            let data = serde_json::to_string(sprint).map_err(internal)?;
+            sqlx::query(
+                "INSERT INTO sprints (user_id, data) VALUES (?, ?)
+                 ON CONFLICT(user_id) DO UPDATE SET data = excluded.data",
+            )
+            .bind(user_id)
+            .bind(&data)
+            .execute(db)
+            .await
+            .map_err(internal)?;
+        }
+        None => {
+            sqlx::query("DELETE FROM sprints WHERE user_id = ?")
+                .bind(user_id)
+                .execute(db)
+                .await
+                .map_err(internal)?;
+        }
+    }
+    Ok(())
+}
+
+/// A practice session with no answers for this long is closed automatically.
+const SESSION_IDLE_MINUTES: i64 = 30;
+
+/// Loads the user's open practice session, closing it first if it has been
+/// idle for too long.
+async fn load_active_session(
+    db: &SqlitePool,
+    user_id: i64,
+) -> Result<Option<Session>, (StatusCode, String)> {
+    let row = sqlx::query(
+        "SELECT data FROM practice_sessions WHERE user_id = ? AND ended_at IS NULL
+         ORDER BY started_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(internal)?;
+
+    let Some(r) = row else {
+        return Ok(None);
+    };
+    let data: String = r.try_get("data").map_err(internal)?;
+    let mut session: Session = serde_json::from_str(&data).map_err(internal)?;
+
+    if session.is_idle(Utc::now(), chrono::Duration::minutes(SESSION_IDLE_MINUTES)) {
+        session.end(session.last_activity());
+        save_session(db, user_id, &session).await?;
+        return Ok(None);
+    }
+    Ok(Some(session))
+}
+
+async fn load_or_start_session(
+    db: &SqlitePool,
+    user_id: i64,
+) -> Result<Session, (StatusCode, String)> {
+    Ok(load_active_session(db, user_id)
+        .await?
+        .unwrap_or_else(|| Session::new(generate_token())))
+}
+
+async fn save_session(
+    db: &SqlitePool,
+    user_id: i64,
+    session: &Session,
+) -> Result<(), (StatusCode, String)> {
+    let data = serde_json::to_string(session).map_err(internal)?;
+    sqlx::query(
+        "INSERT INTO practice_sessions (id, user_id, started_at, ended_at, data)
+         VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET ended_at = excluded.ended_at, data = excluded.data",
+    )
+    .bind(&session.id)
+    .bind(user_id)
+    .bind(session.started_at.to_rfc3339())
+    .bind(session.ended_at.map(|t| t.to_rfc3339()))
+    .bind(&data)
+    .execute(db)
+    .await
+    .map_err(internal)?;
+    Ok(())
+}
+
 // ── Problem selection ─────────────────────────────────────────────────────────
 
-fn pick_problem(sr: &SpacedRepetition, last: Option<&Problem>) -> ProblemDto {
-    let p = sr
-        .get_next_problem(last)
-        .or_else(|| sr.get_extra_practice_problem(last))
+fn pick_problem(
+    sr: &SpacedRepetition,
+    last: Option<&Problem>,
+    focus: Option<&Focus>,
+    mode: AnswerMode,
+    locale: Locale,
+) -> Result<ProblemDto, (StatusCode, String)> {
+    next_problem(sr, last, focus)
+        .map(|p| problem_dto(p, mode, locale))
+        .ok_or_else(nothing_to_practise)
+}
+
+/// The next problem to ask, from the focus if there is one. A focus with
+/// nothing left to ask falls back to the unlocked tables.
+fn next_problem(
+    sr: &SpacedRepetition,
+    last: Option<&Problem>,
+    focus: Option<&Focus>,
+) -> Option<Problem> {
+    next_problem_in(sr, last, focus).or_else(|| next_problem_in(sr, last, None))
+}
+
+fn next_problem_in(
+    sr: &SpacedRepetition,
+    last: Option<&Problem>,
+    focus: Option<&Focus>,
+) -> Option<Problem> {
+    sr.get_next_problem(last, focus)
+        .or_else(|| sr.get_extra_practice_problem(last, focus))
         // If last was the only problem, ignore it and repeat
-        .or_else(|| sr.get_next_problem(None))
-        .or_else(|| sr.get_extra_practice_problem(None))
-        .unwrap_or_else(|| Problem::new(1, 1));
-    ProblemDto { a: p.a, b: p.b }
+        .or_else(|| sr.get_next_problem(None, focus))
+        .or_else(|| sr.get_extra_practice_problem(None, focus))
+}
+
+fn problem_dto(p: Problem, mode: AnswerMode, locale: Locale) -> ProblemDto {
+    let choices = match mode {
+        AnswerMode::Typed => None,
+        AnswerMode::MultipleChoice => {
+            Some(MultipleChoice::new(p, CHOICE_COUNT, rand::random()).options)
+        }
+    };
+    ProblemDto {
+        a: p.a,
+        b: p.b,
+        text: locale.question(&p),
+        words: locale.question_words(&p),
+        rtl: locale.is_rtl(),
+        choices,
+        visual: Visual::new(&p),
+    }
 }
 
 // ── Static file handlers ──────────────────────────────────────────────────────
@@ -237,7 +724,7 @@ async fn register(
     let salt = SaltString::generate(&mut OsRng);
     let password_hash = Argon2::default()
         .hash_password(req.password.as_bytes(), &salt)
-        .map_err(|e| internal(e))?
+        .map_err(internal)?
         .to_string();
 
     let result = sqlx::query(
@@ -279,7 +766,7 @@ async fn login(
     }
 
     let parsed =
-        PasswordHash::new(&stored_hash).map_err(|e| internal(e))?;
+        PasswordHash::new(&stored_hash).map_err(internal)?;
     Argon2::default()
         .verify_password(req.password.as_bytes(), &parsed)
         .map_err(|_| app_err(StatusCode::UNAUTHORIZED, "Invalid username or password"))?;
@@ -307,13 +794,49 @@ async fn logout(
 async fn get_state(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Query(clock): Query<ClockQuery>,
+) -> AppResult<StateResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let sr = load_user_state(&state.db, user_id).await?;
+    let focus = load_focus(&state.db, user_id).await?;
+    let session = load_active_session(&state.db, user_id).await?;
+    let problem = pick_problem(&sr, None, focus.as_ref(), view.mode, view.locale())?;
+
+    Ok(Json(StateResponse {
+        problem,
+        mastered: sr.mastered_count(),
+        total: sr.unlocked_problems(),
+        due: sr.due_count(),
+        grid: sr.grid_status(),
+        focus: focus.map(FocusDto::from),
+        session: session.as_ref().map(SessionDto::from),
+        daily: sr.daily_summary_in(clock.utc_offset_mins()),
+    }))
+}
+
+/// Starts a focused session on the given tables and facts. An empty request
+/// ends focused practice and goes back to everything unlocked.
+async fn set_focus(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Query(clock): Query<ClockQuery>,
+    Json(req): Json<FocusRequest>,
 ) -> AppResult<StateResponse> {
     let user_id = authenticate(&state.db, &headers)
         .await
         .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
 
+    let focus = Some(Focus::new(req.tables, req.facts)).filter(|f| !f.is_empty());
+    save_focus(&state.db, user_id, focus.as_ref()).await?;
+
     let sr = load_user_state(&state.db, user_id).await?;
-    let problem = pick_problem(&sr, None);
+    let session = load_active_session(&state.db, user_id).await?;
+    let problem = pick_problem(&sr, None, focus.as_ref(), view.mode, view.locale())?;
 
     Ok(Json(StateResponse {
         problem,
@@ -321,39 +844,474 @@ async fn get_state(
         total: sr.unlocked_problems(),
         due: sr.due_count(),
         grid: sr.grid_status(),
+        focus: focus.map(FocusDto::from),
+        session: session.as_ref().map(SessionDto::from),
+        daily: sr.daily_summary_in(clock.utc_offset_mins()),
     }))
 }
 
 async fn submit_answer(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Query(clock): Query<ClockQuery>,
     Json(req): Json<AnswerRequest>,
 ) -> AppResult<AnswerResponse> {
     let user_id = authenticate(&state.db, &headers)
         .await
         .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+    let answer = req.answer.value(&view)?;
+    let problem = req.problem()?;
 
     let mut sr = load_user_state(&state.db, user_id).await?;
-    let problem = Problem::new(req.a, req.b);
     let correct_answer = problem.answer();
-    let correct = req.answer == correct_answer;
-
-    sr.record_answer(&problem, correct, req.elapsed_secs);
+    let correct = answer == correct_answer;
+
+    let mistake = sr.record_given_answer_in(
+        &problem,
+        answer,
+        req.elapsed_secs,
+        req.mode,
+        clock.utc_offset_mins(),
+    );
     save_user_state(&state.db, user_id, &sr).await?;
 
-    let next = pick_problem(&sr, Some(&problem));
+    let mut session = load_or_start_session(&state.db, user_id).await?;
+    session.record_with_mode(problem, answer, correct, req.elapsed_secs, req.mode);
+    save_session(&state.db, user_id, &session).await?;
+
+    let focus = load_focus(&state.db, user_id).await?;
+    let next = pick_problem(&sr, Some(&problem), focus.as_ref(), req.mode, view.locale())?;
 
     Ok(Json(AnswerResponse {
         correct,
         correct_answer,
+        user_answer: answer,
+        mistake: mistake.map(MistakeDto::from),
+        hint: (!correct).then(|| sr.hint_for(&problem)),
+        visual: (!correct).then(|| Visual::new(&problem)),
         next_problem: next,
         mastered: sr.mastered_count(),
         total: sr.unlocked_problems(),
         due: sr.due_count(),
         grid: sr.grid_status(),
+        focus: focus.map(FocusDto::from),
+        session: SessionDto::from(&session),
+        daily: sr.daily_summary_in(clock.utc_offset_mins()),
+    }))
+}
+
+/// Reads typed text as an answer without recording anything, so the web app
+/// checks corrections with the same parser as answers.
+async fn parse_typed_answer(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Json(req): Json<ParseRequest>,
+) -> AppResult<ParseResponse> {
+    authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+    let value = AnswerInput::Text(req.text).value(&view)?;
+    Ok(Json(ParseResponse { value }))
+}
+
+/// Applies answers given while offline, in order and as of when they were
+/// given, with one load and save. Answers already applied are skipped by id,
+/// so a client can send the same batch again after a dropped connection.
+async fn submit_answer_batch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(clock): Query<ClockQuery>,
+    Json(req): Json<BatchAnswerRequest>,
+) -> AppResult<BatchAnswerResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    if req.answers.len() > MAX_BATCH_ANSWERS {
+        return Err(app_err(
+            StatusCode::BAD_REQUEST,
+            format!("At most {} answers per batch", MAX_BATCH_ANSWERS),
+        ));
+    }
+    if let Some(bad) = req.answers.iter().find(|a| a.problem.index().is_none()) {
+        return Err(app_err(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown problem {} in answer {}", bad.problem.key(), bad.id),
+        ));
+    }
+
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    let results = sr.apply_answers(&req.answers, clock.utc_offset_mins());
+    save_user_state(&state.db, user_id, &sr).await?;
+
+    let mut session = load_or_start_session(&state.db, user_id).await?;
+    for (answer, result) in req.answers.iter().zip(&results).filter(|(_, r)| r.applied) {
+        session.record_at(
+            answer.problem,
+            answer.answer,
+            result.correct,
+            answer.response_secs,
+            answer.mode,
+            answer.answered_at.min(Utc::now()),
+        );
+    }
+    save_session(&state.db, user_id, &session).await?;
+
+    Ok(Json(BatchAnswerResponse {
+        results,
+        mastered: sr.mastered_count(),
+        total: sr.unlocked_problems(),
+        due: sr.due_count(),
+        grid: sr.grid_status(),
+        session: SessionDto::from(&session),
+        daily: sr.daily_summary_in(clock.utc_offset_mins()),
+    }))
+}
+
+/// Merges progress from another app, such as the desktop app, into the
+/// learner's progress here, and returns the result for the app to merge back
+/// into its own. See `SpacedRepetition::merge`.
+async fn sync_progress(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> AppResult<SpacedRepetition> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let theirs = SpacedRepetition::from_json(&body).map_err(|e| match e {
+        LoadError::TooNew { .. } => app_err(StatusCode::CONFLICT, e),
+        LoadError::Invalid(_) => app_err(StatusCode::BAD_REQUEST, e),
+    })?;
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    sr.merge(&theirs);
+    save_user_state(&state.db, user_id, &sr).await?;
+    Ok(Json(sr))
+}
+
+/// Takes back the last answer shortly after it was given, for typos, and
+/// asks the same problem again.
+async fn undo_answer(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Query(clock): Query<ClockQuery>,
+) -> AppResult<StateResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    // A sprint has already scored its answers.
+    if load_sprint(&state.db, user_id).await?.is_some() {
+        return Err(app_err(StatusCode::CONFLICT, "Answers can't be undone during a sprint"));
+    }
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    let problem = sr
+        .undo_last_answer()
+        .ok_or_else(|| app_err(StatusCode::CONFLICT, "Nothing to undo"))?;
+    save_user_state(&state.db, user_id, &sr).await?;
+
+    let mut session = load_active_session(&state.db, user_id).await?;
+    if let Some(session) = &mut session {
+        session.undo_last();
+        save_session(&state.db, user_id, session).await?;
+    }
+
+    let focus = load_focus(&state.db, user_id).await?;
+    Ok(Json(StateResponse {
+        problem: problem_dto(problem, view.mode, view.locale()),
+        mastered: sr.mastered_count(),
+        total: sr.unlocked_problems(),
+        due: sr.due_count(),
+        grid: sr.grid_status(),
+        focus: focus.map(FocusDto::from),
+        session: session.as_ref().map(SessionDto::from),
+        daily: sr.daily_summary_in(clock.utc_offset_mins()),
+    }))
+}
+
+async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<SettingsRequest>,
+) -> AppResult<SettingsResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    if let Some(enabled) = req.commutative {
+        sr.set_commutative(enabled);
+    }
+    save_user_state(&state.db, user_id, &sr).await?;
+
+    Ok(Json(SettingsResponse {
+        commutative: sr.is_commutative(),
+    }))
+}
+
+async fn set_goal(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(clock): Query<ClockQuery>,
+    Json(req): Json<GoalRequest>,
+) -> AppResult<DailySummary> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    if !(1..=1000).contains(&req.goal.target()) {
+        return Err(app_err(StatusCode::BAD_REQUEST, "Goal must be between 1 and 1000"));
+    }
+
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    sr.set_daily_goal_in(req.goal, clock.utc_offset_mins());
+    if let Some(enabled) = req.streak_freeze {
+        sr.set_streak_freeze(enabled);
+    }
+    save_user_state(&state.db, user_id, &sr).await?;
+
+    Ok(Json(sr.daily_summary_in(clock.utc_offset_mins())))
+}
+
+async fn start_sprint(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Json(req): Json<SprintStartRequest>,
+) -> AppResult<SprintStateResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let valid = match req.limit {
+        SprintLimit::Seconds(s) => (10..=600).contains(&s),
+        SprintLimit::Problems(n) => (1..=200).contains(&n),
+    };
+    if !valid {
+        return Err(app_err(StatusCode::BAD_REQUEST, "Unsupported sprint length"));
+    }
+
+    let sr = load_user_state(&state.db, user_id).await?;
+    let focus = load_focus(&state.db, user_id).await?;
+    let mut sprint = Sprint::new(req.limit, focus);
+    let problem = next_problem(&sr, None, sprint.focus.as_ref()).ok_or_else(nothing_to_practise)?;
+    sprint.ask(problem, Utc::now());
+    save_sprint(&state.db, user_id, Some(&sprint)).await?;
+
+    let best_score = sr
+        .sprint_records()
+        .best(sprint.limit, sprint.focus.as_ref())
+        .map(|b| b.score);
+
+    Ok(Json(SprintStateResponse {
+        problem: problem_dto(problem, view.mode, view.locale()),
+        limit: sprint.limit,
+        remaining_secs: sprint.remaining_secs(Utc::now()),
+        remaining_problems: sprint.remaining_problems(),
+        score: sprint.score,
+        correct: sprint.correct,
+        wrong: sprint.wrong,
+        best_score,
     }))
 }
 
+/// Records a sprint answer in both the sprint and the learner's normal
+/// progress. Answers that arrive after a timed sprint has run out only end it.
+/// Only the problem the sprint last gave out can be answered, once, and the
+/// answer is timed from when it was given out rather than by the client.
+async fn submit_sprint_answer(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(view): Query<ViewQuery>,
+    Query(clock): Query<ClockQuery>,
+    Json(req): Json<AnswerRequest>,
+) -> AppResult<SprintAnswerResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+    let answer = req.answer.value(&view)?;
+    let problem = req.problem()?;
+
+    let mut sprint = load_sprint(&state.db, user_id)
+        .await?
+        .ok_or_else(|| app_err(StatusCode::CONFLICT, "No sprint in progress"))?;
+    let answered_at = Utc::now();
+    let elapsed_secs = sprint
+        .response_secs(&problem, answered_at)
+        .ok_or_else(|| app_err(StatusCode::CONFLICT, "That isn't the sprint's current problem"))?;
+    let mut sr = load_user_state(&state.db, user_id).await?;
+    let correct_answer = problem.answer();
+    let correct = answer == correct_answer;
+
+    let mut session = load_or_start_session(&state.db, user_id).await?;
+    let mut points = 0;
+    if !sprint.is_over(answered_at) {
+        sr.record_sprint_answer_in(
+            &problem,
+            answer,
+            elapsed_secs,
+            req.mode,
+            clock.utc_offset_mins(),
+        );
+        session.record_with_mode(problem, answer, correct, elapsed_secs, req.mode);
+        save_session(&state.db, user_id, &session).await?;
+        points = sprint.record(correct, elapsed_secs);
+    }
+
+    let now = Utc::now();
+    let next_problem = next_problem(&sr, Some(&problem), sprint.focus.as_ref());
+    let finished = if sprint.is_over(now) {
+        let result = sprint.finish(now);
+        let new_best = sr.record_sprint(result.clone());
+        save_sprint(&state.db, user_id, None).await?;
+        Some(SprintFinishedResponse { result, new_best })
+    } else {
+        if let Some(next_problem) = next_problem {
+            sprint.ask(next_problem, now);
+        }
+        save_sprint(&state.db, user_id, Some(&sprint)).await?;
+        None
+    };
+    save_user_state(&state.db, user_id, &sr).await?;
+
+    let next_problem = next_problem
+        .filter(|_| finished.is_none())
+        .map(|p| problem_dto(p, req.mode, view.locale()));
+
+    Ok(Json(SprintAnswerResponse {
+        correct,
+        correct_answer,
+        points,
+        score: sprint.score,
+        next_problem,
+        remaining_secs: sprint.remaining_secs(now),
+        remaining_problems: sprint.remaining_problems(),
+        finished,
+        session: SessionDto::from(&session),
+    }))
+}
+
+/// Ends the current sprint early, or when the client's countdown reaches zero.
+async fn finish_sprint(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<SprintFinishedResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let sprint = load_sprint(&state.db, user_id)
+        .await?
+        .ok_or_else(|| app_err(StatusCode::CONFLICT, "No sprint in progress"))?;
+    let mut sr = load_user_state(&state.db, user_id).await?;
+
+    let result = sprint.finish(Utc::now());
+    let new_best = sr.record_sprint(result.clone());
+    save_user_state(&state.db, user_id, &sr).await?;
+    save_sprint(&state.db, user_id, None).await?;
+
+    Ok(Json(SprintFinishedResponse { result, new_best }))
+}
+
+async fn sprint_history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<SprintHistoryResponse> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let sr = load_user_state(&state.db, user_id).await?;
+    let records = sr.sprint_records();
+
+    Ok(Json(SprintHistoryResponse {
+        history: records.history().to_vec(),
+        bests: records.bests().cloned().collect(),
+    }))
+}
+
+/// Closes the current practice session so the next answer starts a new one.
+async fn end_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    if let Some(mut session) = load_active_session(&state.db, user_id).await? {
+        session.end(Utc::now());
+        save_session(&state.db, user_id, &session).await?;
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Finished sessions, most recent first.
+async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Vec<Session>> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let rows = sqlx::query(
+        "SELECT data FROM practice_sessions WHERE user_id = ? AND ended_at IS NOT NULL
+         ORDER BY started_at DESC LIMIT 50",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(internal)?;
+
+    let sessions = rows
+        .iter()
+        .map(|r| {
+            let data: String = r.try_get("data").map_err(internal)?;
+            serde_json::from_str(&data).map_err(internal)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Json(sessions))
+}
+
+/// A printable worksheet of the learner's due and weakest facts, as HTML.
+async fn worksheet(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<WorksheetQuery>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let user_id = authenticate(&state.db, &headers)
+        .await
+        .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
+
+    let defaults = WorksheetOptions::default();
+    let options = WorksheetOptions {
+        count: query.count.unwrap_or(defaults.count),
+        columns: query.columns.unwrap_or(defaults.columns),
+        answer_key: query.answer_key.unwrap_or(defaults.answer_key),
+        seed: query.seed.unwrap_or_else(rand::random),
+    };
+    if !(1..=MAX_PROBLEMS).contains(&options.count) {
+        return Err(app_err(
+            StatusCode::BAD_REQUEST,
+            format!("Count must be between 1 and {}", MAX_PROBLEMS),
+        ));
+    }
+    if !(1..=MAX_COLUMNS).contains(&options.columns) {
+        return Err(app_err(
+            StatusCode::BAD_REQUEST,
+            format!("Columns must be between 1 and {}", MAX_COLUMNS),
+        ));
+    }
+
+    let sr = load_user_state(&state.db, user_id).await?;
+    Ok(Html(sr.worksheet(options).to_html("Times tables worksheet")))
+}
+
 async fn reset_progress(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -362,7 +1320,14 @@ async fn reset_progress(
         .await
         .ok_or_else(|| app_err(StatusCode::UNAUTHORIZED, "Unauthorized"))?;
 
-    let sr = SpacedRepetition::new();
+    if let Some(mut session) = load_active_session(&state.db, user_id).await? {
+        session.end(Utc::now());
+        save_session(&state.db, user_id, &session).await?;
+    }
+
+    // Starting over needs nothing from the old state, which may not load.
+    let mut sr = SpacedRepetition::new();
+    sr.reset();
     save_user_state(&state.db, user_id, &sr).await?;
     Ok(StatusCode::OK)
 }
@@ -500,7 +1465,7 @@ async fn find_or_create_google_user(
         .await
         .map_err(internal)?
     {
-        return Ok(row.try_get("id").map_err(internal)?);
+        return row.try_get("id").map_err(internal);
     }
 
     if let Some(row) = sqlx::query("SELECT id FROM users WHERE username = ?")
@@ -528,7 +1493,7 @@ async fn find_or_create_google_user(
     .await
     .map_err(internal)?;
 
-    Ok(row.try_get("id").map_err(internal)?)
+    row.try_get("id").map_err(internal)
 }
 
 // ── DB setup ──────────────────────────────────────────────────────────────────
@@ -610,6 +1575,40 @@ async fn migrate_db(pool: &SqlitePool) {
     .execute(pool)
     .await
     .expect("Could not create google_id index");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS focus (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id),
+            data TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .expect("Could not create focus table");
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS sprints (
+            user_id INTEGER PRIMARY KEY REFERENCES users(id),
+            data TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .expect("Could not create sprints table");
+
+    // Practice sessions; not to be confused with `sessions`, which holds login tokens.
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS practice_sessions (
+            id TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            data TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .expect("Could not create practice_sessions table");
 }
 
 // ── Main ──────────────────────────────────────────────────────────────────────
@@ -640,7 +1639,21 @@ async fn main() {
         .route("/api/logout", post(logout))
         .route("/api/state", get(get_state))
         .route("/api/answer", post(submit_answer))
+        .route("/api/answers", post(submit_answer_batch))
+        .route("/api/parse", post(parse_typed_answer))
+        .route("/api/undo", post(undo_answer))
+        .route("/api/sync", post(sync_progress))
+        .route("/api/focus", post(set_focus))
+        .route("/api/goal", post(set_goal))
+        .route("/api/settings", post(update_settings))
+        .route("/api/sprint/start", post(start_sprint))
+        .route("/api/sprint/answer", post(submit_sprint_answer))
+        .route("/api/sprint/finish", post(finish_sprint))
+        .route("/api/sprint/history", get(sprint_history))
         .route("/api/reset", post(reset_progress))
+        .route("/api/session/end", post(end_session))
+        .route("/api/sessions", get(list_sessions))
+        .route("/api/worksheet", get(worksheet))
         .route("/api/config", get(get_config))
         .route("/", get(serve_index))
         .route("/style.css", get(serve_css))
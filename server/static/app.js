@@ -3,19 +3,27 @@
 // ── State ─────────────────────────────────────────────────────────────────────
 
 const state = {
-  problem: null,           // { a, b }
+  problem: null,           // { a, b, choices? }
+  multipleChoice: localStorage.getItem('answerMode') === 'multiple_choice',
+  showVisual: localStorage.getItem('showVisual') === 'true',
+  showWords: localStorage.getItem('showWords') === 'true',
+  lang: localStorage.getItem('lang') || 'en',
+  operator: localStorage.getItem('operator') || '',  // '' for the language's usual sign
   awaitingCorrection: false,
   correctAnswer: null,
   pendingNextProblem: null, // next problem to show after correction
+  undoTimer: null,         // hides the undo button when its window closes
   problemStartMs: 0,
-  streak: 0,
-  sessionCorrect: 0,
-  sessionWrong: 0,
+  session: null,           // { id, streak, best_streak, correct, wrong } from the server
   mastered: 0,
   total: 0,
   due: 0,
+  daily: null,             // { goal, done, target, met, day_streak, freezes }
 };
 
+// Matches UNDO_WINDOW_SECS in tt_core.
+const UNDO_WINDOW_MS = 30000;
+
 // ── DOM refs ──────────────────────────────────────────────────────────────────
 
 const $ = id => document.getElementById(id);
@@ -28,18 +36,34 @@ const loginBtn        = $('login-btn');
 const registerBtn     = $('register-btn');
 const authError       = $('auth-error');
 const problemText     = $('problem-text');
+const problemWords    = $('problem-words');
+const problemVisual   = $('problem-visual');
+const wordsToggle     = $('words-toggle');
+const langSelect      = $('lang-select');
+const operatorSelect  = $('operator-select');
+const visualToggle    = $('visual-toggle');
+const correctionVisual = $('correction-visual');
 const normalMode      = $('normal-mode');
 const answerInput     = $('answer-input');
+const answerError     = $('answer-error');
 const submitBtn       = $('submit-btn');
+const choiceMode      = $('choice-mode');
+const choiceToggle    = $('choice-toggle');
 const correctionMode  = $('correction-mode');
 const incorrectMsg    = $('incorrect-msg');
+const hintMsg         = $('hint-msg');
+const mistakeMsg      = $('mistake-msg');
 const correctionInput = $('correction-input');
+const undoBtn         = $('undo-btn');
 const streakEl        = $('streak');
 const masteredEl      = $('mastered');
 const totalEl         = $('total');
 const dueEl           = $('due');
 const sessionCorrectEl = $('session-correct');
 const sessionWrongEl  = $('session-wrong');
+const dailyDoneEl     = $('daily-done');
+const dailyTargetEl   = $('daily-target');
+const dayStreakEl     = $('day-streak');
 const resetBtn        = $('reset-btn');
 const resetConfirm    = $('reset-confirm');
 const resetYes        = $('reset-yes');
@@ -59,16 +83,34 @@ function authHeaders() {
   return t ? { 'Authorization': `Bearer ${t}` } : {};
 }
 
+// Every request says how far the learner's clock is from UTC, so answers,
+// the goal and the streak follow their day rather than the server's.
+function withClock(path) {
+  const sep = path.includes('?') ? '&' : '?';
+  return `${path}${sep}utc_offset=${-new Date().getTimezoneOffset()}`;
+}
+
 async function apiPost(path, body) {
-  return fetch(path, {
+  return fetch(withClock(path), {
     method: 'POST',
     headers: { 'Content-Type': 'application/json', ...authHeaders() },
     body: JSON.stringify(body),
   });
 }
 
+function answerMode() {
+  return state.multipleChoice ? 'multiple_choice' : 'typed';
+}
+
+// Query string telling the server how to present the next problem.
+function viewParams() {
+  const params = new URLSearchParams({ mode: answerMode(), lang: state.lang });
+  if (state.operator) params.set('operator', state.operator);
+  return params;
+}
+
 async function apiGet(path) {
-  return fetch(path, { headers: authHeaders() });
+  return fetch(withClock(path), { headers: authHeaders() });
 }
 
 // ── View helpers ──────────────────────────────────────────────────────────────
@@ -89,39 +131,108 @@ function setAuthError(msg) {
   authError.classList.toggle('hidden', !msg);
 }
 
+function setAnswerError(msg) {
+  answerError.textContent = msg;
+  answerError.classList.toggle('hidden', !msg);
+}
+
 function showNormalMode() {
   state.awaitingCorrection = false;
-  normalMode.classList.remove('hidden');
+  setAnswerError('');
   correctionMode.classList.add('hidden');
-  answerInput.value = '';
-  answerInput.focus();
+  const choices = state.problem && state.problem.choices;
+  normalMode.classList.toggle('hidden', !!choices);
+  choiceMode.classList.toggle('hidden', !choices);
+  if (choices) {
+    renderChoices(choices);
+  } else {
+    answerInput.value = '';
+    answerInput.focus();
+  }
 }
 
-function showCorrectionMode(userAnswer, correctAnswer, nextProblem) {
+function renderChoices(choices) {
+  choiceMode.innerHTML = '';
+  choices.forEach(value => {
+    const btn = document.createElement('button');
+    btn.className = 'choice-btn';
+    btn.textContent = value;
+    btn.addEventListener('click', () => submitAnswer(value));
+    choiceMode.appendChild(btn);
+  });
+}
+
+function showCorrectionMode(userAnswer, correctAnswer, nextProblem, hint, visual, mistake) {
   state.awaitingCorrection = true;
   state.correctAnswer = correctAnswer;
   state.pendingNextProblem = nextProblem;
   incorrectMsg.textContent = `${userAnswer} is wrong. Type the answer: ${correctAnswer}`;
+  mistakeMsg.textContent = mistake ? mistake.text : '';
+  hintMsg.textContent = hint ? `Tip: ${hint.text}` : '';
+  hintMsg.classList.toggle('hidden', !hint);
+  renderVisual(correctionVisual, visual, true);
   normalMode.classList.add('hidden');
+  choiceMode.classList.add('hidden');
   correctionMode.classList.remove('hidden');
   correctionInput.value = '';
   correctionInput.focus();
+  setAnswerError('');
+  // The server only takes an answer back for a short while after it's given.
+  undoBtn.classList.remove('hidden');
+  clearTimeout(state.undoTimer);
+  state.undoTimer = setTimeout(() => undoBtn.classList.add('hidden'), UNDO_WINDOW_MS);
 }
 
 function displayProblem(problem) {
   state.problem = problem;
   state.problemStartMs = Date.now();
-  problemText.textContent = `${problem.a} × ${problem.b} = ?`;
+  problemText.textContent = problem.text;
+  problemText.dir = problem.rtl ? 'rtl' : 'ltr';
+  problemWords.textContent = problem.words;
+  problemWords.dir = problem.rtl ? 'rtl' : 'ltr';
+  problemWords.classList.toggle('hidden', !state.showWords);
+  renderVisual(problemVisual, state.showVisual ? problem.visual : null);
   showNormalMode();
 }
 
+// Draws a fact as blocks of dots, one block per part of the split, with the
+// caption underneath; `answered` shows the caption worked through to the
+// answer. Hides the container when there is nothing to draw.
+function renderVisual(container, visual, answered = false) {
+  container.innerHTML = '';
+  container.classList.toggle('hidden', !visual);
+  if (!visual) return;
+  visual.parts.forEach(part => {
+    const block = document.createElement('div');
+    block.className = 'visual-part';
+    block.style.gridTemplateColumns = `repeat(${part.cols}, 10px)`;
+    for (let i = 0; i < part.rows * part.cols; i++) {
+      const dot = document.createElement('span');
+      dot.className = 'visual-dot';
+      block.appendChild(dot);
+    }
+    container.appendChild(block);
+  });
+  const caption = document.createElement('div');
+  caption.className = 'visual-caption';
+  caption.textContent = answered ? visual.worked : visual.caption;
+  container.appendChild(caption);
+}
+
 function updateStats() {
-  streakEl.textContent = state.streak;
+  const session = state.session || { streak: 0, correct: 0, wrong: 0 };
+  streakEl.textContent = session.streak;
   masteredEl.textContent = state.mastered;
   totalEl.textContent = state.total;
   dueEl.textContent = state.due;
-  sessionCorrectEl.textContent = state.sessionCorrect;
-  sessionWrongEl.textContent = state.sessionWrong;
+  sessionCorrectEl.textContent = session.correct;
+  sessionWrongEl.textContent = session.wrong;
+  if (state.daily) {
+    dailyDoneEl.textContent = state.daily.done;
+    dailyTargetEl.textContent = state.daily.target;
+    dayStreakEl.textContent = state.daily.day_streak;
+    dailyDoneEl.classList.toggle('correct-count', state.daily.met);
+  }
 }
 
 function renderGrid(grid) {
@@ -139,7 +250,7 @@ function renderGrid(grid) {
 // ── Auth ──────────────────────────────────────────────────────────────────────
 
 async function loadState() {
-  const res = await apiGet('/api/state');
+  const res = await apiGet(`/api/state?${viewParams()}`);
   if (res.status === 401) {
     localStorage.removeItem('token');
     showAuth();
@@ -153,6 +264,8 @@ async function loadState() {
   state.mastered = data.mastered;
   state.total = data.total;
   state.due = data.due;
+  state.session = data.session;
+  state.daily = data.daily;
   updateStats();
   renderGrid(data.grid);
   displayProblem(data.problem);
@@ -173,10 +286,6 @@ async function doAuth(endpoint) {
     const data = await res.json();
     localStorage.setItem('token', data.token);
     passwordInput.value = '';
-    // Reset session stats on new login
-    state.streak = 0;
-    state.sessionCorrect = 0;
-    state.sessionWrong = 0;
     await loadState();
   } else {
     const msg = await res.text();
@@ -195,23 +304,25 @@ registerBtn.addEventListener('click', () => doAuth('/api/register'));
 
 // ── Answer submission ─────────────────────────────────────────────────────────
 
-async function submitAnswer() {
+// `picked` is the chosen option in multiple-choice mode; otherwise the typed
+// text is sent as it is and read by the server, which says if it can't.
+async function submitAnswer(picked) {
   if (!state.problem) return;
-  const raw = answerInput.value.trim();
-  if (raw === '') return;
-  const answer = parseInt(raw, 10);
-  if (isNaN(answer)) {
-    answerInput.value = '';
-    return;
+  const mode = state.problem.choices ? 'multiple_choice' : 'typed';
+  let answer = picked;
+  if (mode === 'typed') {
+    answer = answerInput.value;
+    if (answer.trim() === '') return;
   }
 
   const elapsedSecs = (Date.now() - state.problemStartMs) / 1000;
 
-  const res = await apiPost('/api/answer', {
+  const res = await apiPost(`/api/answer?${viewParams()}`, {
     a: state.problem.a,
     b: state.problem.b,
     answer,
     elapsed_secs: elapsedSecs,
+    mode,
   });
 
   if (res.status === 401) {
@@ -220,37 +331,77 @@ async function submitAnswer() {
     return;
   }
 
+  if (res.status === 400) {
+    setAnswerError(await res.text());
+    answerInput.focus();
+    return;
+  }
   if (!res.ok) return;
 
   const data = await res.json();
   state.mastered = data.mastered;
   state.total = data.total;
   state.due = data.due;
+  state.session = data.session;
+  state.daily = data.daily;
   renderGrid(data.grid);
+  updateStats();
 
   if (data.correct) {
-    state.streak += 1;
-    state.sessionCorrect += 1;
-    updateStats();
     displayProblem(data.next_problem);
   } else {
-    state.streak = 0;
-    state.sessionWrong += 1;
-    updateStats();
-    showCorrectionMode(answer, data.correct_answer, data.next_problem);
+    showCorrectionMode(
+      data.user_answer, data.correct_answer, data.next_problem, data.hint, data.visual, data.mistake,
+    );
   }
 }
 
-function checkCorrection() {
-  const raw = correctionInput.value.trim();
-  if (raw === '') return;
-  const typed = parseInt(raw, 10);
-  if (typed === state.correctAnswer) {
+// The server reads the correction with the same parser as answers, so digits
+// from other scripts and number words work here too.
+async function checkCorrection() {
+  const text = correctionInput.value;
+  if (text.trim() === '') return;
+  const res = await apiPost(`/api/parse?${viewParams()}`, { text });
+  if (res.status === 401) {
+    localStorage.removeItem('token');
+    showAuth();
+    return;
+  }
+  if (res.status === 400) {
+    setAnswerError(await res.text());
+    return;
+  }
+  if (!res.ok) return;
+  setAnswerError('');
+  const { value } = await res.json();
+  if (value === state.correctAnswer) {
     displayProblem(state.pendingNextProblem);
   }
 }
 
-submitBtn.addEventListener('click', submitAnswer);
+async function undoAnswer() {
+  const res = await apiPost(`/api/undo?${viewParams()}`, {});
+  if (res.status === 401) {
+    localStorage.removeItem('token');
+    showAuth();
+    return;
+  }
+  undoBtn.classList.add('hidden');
+  if (!res.ok) return;
+
+  const data = await res.json();
+  state.mastered = data.mastered;
+  state.total = data.total;
+  state.due = data.due;
+  state.session = data.session;
+  state.daily = data.daily;
+  renderGrid(data.grid);
+  updateStats();
+  displayProblem(data.problem);
+}
+
+submitBtn.addEventListener('click', () => submitAnswer());
+undoBtn.addEventListener('click', undoAnswer);
 
 answerInput.addEventListener('keydown', e => {
   if (e.key === 'Enter') submitAnswer();
@@ -260,6 +411,81 @@ correctionInput.addEventListener('keydown', e => {
   if (e.key === 'Enter') checkCorrection();
 });
 
+// Number keys pick an option in multiple-choice mode.
+document.addEventListener('keydown', e => {
+  if (practiceView.classList.contains('hidden') || choiceMode.classList.contains('hidden')) return;
+  const n = parseInt(e.key, 10);
+  const choices = state.problem && state.problem.choices;
+  if (choices && n >= 1 && n <= choices.length) submitAnswer(choices[n - 1]);
+});
+
+// ── Display options ───────────────────────────────────────────────────────────
+
+visualToggle.checked = state.showVisual;
+visualToggle.addEventListener('change', () => {
+  state.showVisual = visualToggle.checked;
+  localStorage.setItem('showVisual', state.showVisual);
+  if (state.problem) {
+    renderVisual(problemVisual, state.showVisual ? state.problem.visual : null);
+  }
+});
+
+wordsToggle.checked = state.showWords;
+wordsToggle.addEventListener('change', () => {
+  state.showWords = wordsToggle.checked;
+  localStorage.setItem('showWords', state.showWords);
+  problemWords.classList.toggle('hidden', !state.showWords);
+});
+
+langSelect.value = state.lang;
+operatorSelect.value = state.operator;
+[langSelect, operatorSelect].forEach(el => {
+  el.addEventListener('change', async () => {
+    state.lang = langSelect.value;
+    state.operator = operatorSelect.value;
+    localStorage.setItem('lang', state.lang);
+    localStorage.setItem('operator', state.operator);
+    await loadState();
+  });
+});
+
+choiceToggle.checked = state.multipleChoice;
+choiceToggle.addEventListener('change', async () => {
+  state.multipleChoice = choiceToggle.checked;
+  localStorage.setItem('answerMode', answerMode());
+  await loadState();
+});
+
+// ── Worksheet ─────────────────────────────────────────────────────────────────
+
+$('ws-print').addEventListener('click', async () => {
+  const wsError = $('ws-error');
+  const params = new URLSearchParams({
+    count: $('ws-count').value,
+    columns: $('ws-columns').value,
+    answer_key: $('ws-answer-key').checked,
+  });
+  const seed = $('ws-seed').value.trim();
+  if (seed !== '') params.set('seed', seed);
+
+  // Open the window now; browsers block pop-ups opened after an await.
+  const win = window.open('', '_blank');
+  const res = await apiGet(`/api/worksheet?${params}`);
+  if (!res.ok) {
+    if (win) win.close();
+    wsError.textContent = (await res.text()) || 'Could not make the worksheet.';
+    wsError.classList.remove('hidden');
+    return;
+  }
+  wsError.classList.add('hidden');
+  const html = await res.text();
+  if (!win) return;
+  win.document.write(html);
+  win.document.close();
+  win.focus();
+  win.print();
+});
+
 // ── Reset ─────────────────────────────────────────────────────────────────────
 
 resetBtn.addEventListener('click', () => {
@@ -284,15 +510,13 @@ resetYes.addEventListener('click', async () => {
   }
   if (!res.ok) return;
 
-  state.streak = 0;
-  state.sessionCorrect = 0;
-  state.sessionWrong = 0;
   await loadState();
 });
 
 // ── Logout ────────────────────────────────────────────────────────────────────
 
 logoutBtn.addEventListener('click', async () => {
+  await apiPost('/api/session/end', {});
   await apiPost('/api/logout', {});
   localStorage.removeItem('token');
   showAuth();